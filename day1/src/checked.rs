@@ -0,0 +1,202 @@
+use std::fmt::Debug;
+
+use crate::{error::ScoreError, LocationIds};
+
+/// The integer operations needed to score location ids without wrapping or panicking on overflow
+pub trait CheckedScore: Copy + Ord + Debug {
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+    fn checked_sub(self, rhs: Self) -> Option<Self>;
+    fn checked_mul(self, rhs: Self) -> Option<Self>;
+    fn saturating_sub(self, rhs: Self) -> Self;
+    fn saturating_mul(self, rhs: Self) -> Self;
+    fn checked_from_count(count: usize) -> Option<Self>;
+    fn saturating_from_count(count: usize) -> Self;
+}
+
+macro_rules! impl_checked_score {
+    ($($t:ty),*) => {
+        $(
+            impl CheckedScore for $t {
+                fn checked_add(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_add(self, rhs)
+                }
+                fn checked_sub(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_sub(self, rhs)
+                }
+                fn checked_mul(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_mul(self, rhs)
+                }
+                fn saturating_sub(self, rhs: Self) -> Self {
+                    <$t>::saturating_sub(self, rhs)
+                }
+                fn saturating_mul(self, rhs: Self) -> Self {
+                    <$t>::saturating_mul(self, rhs)
+                }
+                fn checked_from_count(count: usize) -> Option<Self> {
+                    count.try_into().ok()
+                }
+                fn saturating_from_count(count: usize) -> Self {
+                    count.try_into().unwrap_or(<$t>::MAX)
+                }
+            }
+        )*
+    };
+}
+
+impl_checked_score!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+impl<T> LocationIds<T>
+where
+    T: CheckedScore,
+{
+    /// Like [`LocationIds::get_differences`], but fails on the first pair whose difference
+    /// overflows
+    pub fn checked_differences(&self) -> Result<Vec<T>, ScoreError<T>> {
+        self.get_pairings()
+            .into_iter()
+            .map(|(&left, &right)| {
+                checked_difference(left, right)
+                    .ok_or(ScoreError::DifferenceOverflow { left, right })
+            })
+            .collect()
+    }
+
+    /// Like [`LocationIds::get_differences`], but differences that overflow are clamped to the
+    /// largest (or smallest) value of `T`
+    pub fn saturating_differences(&self) -> Vec<T> {
+        self.get_pairings()
+            .into_iter()
+            .map(|(&left, &right)| {
+                if right > left {
+                    right.saturating_sub(left)
+                } else {
+                    left.saturating_sub(right)
+                }
+            })
+            .collect()
+    }
+
+    /// Like [`LocationIds::get_similarities`], but fails on the first id whose count or
+    /// similarity does not fit in `T`
+    pub fn checked_similarities(&self) -> Result<Vec<T>, ScoreError<T>> {
+        self.get_occurrences()
+            .into_iter()
+            .map(|(&id, count)| checked_similarity(id, count))
+            .collect()
+    }
+
+    /// Like [`LocationIds::get_similarities`], but similarities that overflow are clamped to the
+    /// largest (or smallest) value of `T`
+    pub fn saturating_similarities(&self) -> Vec<T> {
+        self.get_occurrences()
+            .into_iter()
+            .map(|(&id, count)| id.saturating_mul(T::saturating_from_count(count)))
+            .collect()
+    }
+
+    /// The sum of [`LocationIds::checked_differences`], failing if the sum overflows
+    pub fn checked_difference_sum(&self) -> Result<T, ScoreError<T>> {
+        self.get_pairings()
+            .into_iter()
+            .try_fold(zero::<T>(), |sum, (&left, &right)| {
+                let difference = checked_difference(left, right)
+                    .ok_or(ScoreError::DifferenceOverflow { left, right })?;
+                sum.checked_add(difference)
+                    .ok_or(ScoreError::DifferenceSumOverflow { left, right })
+            })
+    }
+
+    /// The sum of [`LocationIds::checked_similarities`], failing if the sum overflows
+    pub fn checked_similarity_sum(&self) -> Result<T, ScoreError<T>> {
+        self.get_occurrences()
+            .into_iter()
+            .try_fold(zero::<T>(), |sum, (&id, count)| {
+                sum.checked_add(checked_similarity(id, count)?)
+                    .ok_or(ScoreError::SimilaritySumOverflow { id })
+            })
+    }
+}
+
+pub(crate) fn zero<T>() -> T
+where
+    T: CheckedScore,
+{
+    T::saturating_from_count(0)
+}
+
+pub(crate) fn checked_difference<T>(left: T, right: T) -> Option<T>
+where
+    T: CheckedScore,
+{
+    if right > left {
+        right.checked_sub(left)
+    } else {
+        left.checked_sub(right)
+    }
+}
+
+pub(crate) fn checked_similarity<T>(id: T, count: usize) -> Result<T, ScoreError<T>>
+where
+    T: CheckedScore,
+{
+    let count_t = T::checked_from_count(count).ok_or(ScoreError::CountOverflow { id, count })?;
+    id.checked_mul(count_t)
+        .ok_or(ScoreError::SimilarityOverflow { id, count })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = include_str!("test_input.txt");
+
+    #[test]
+    fn in_range() {
+        let location_ids: LocationIds<u8> = INPUT.parse().unwrap();
+        assert_eq!(
+            location_ids.checked_differences(),
+            Ok(location_ids.get_differences())
+        );
+        assert_eq!(
+            location_ids.checked_similarities(),
+            Ok(location_ids.get_similarities())
+        );
+        assert_eq!(location_ids.checked_difference_sum(), Ok(11));
+        assert_eq!(location_ids.checked_similarity_sum(), Ok(31));
+    }
+
+    #[test]
+    fn overflows() {
+        let location_ids: LocationIds<i8> = "100 -100\n90 90\n90 90".parse().unwrap();
+        assert_eq!(
+            location_ids.checked_differences(),
+            Err(ScoreError::DifferenceOverflow {
+                left: 90,
+                right: -100
+            })
+        );
+        assert_eq!(location_ids.saturating_differences(), vec![127, 0, 10]);
+        assert_eq!(
+            location_ids.checked_similarities(),
+            Err(ScoreError::SimilarityOverflow { id: 90, count: 2 })
+        );
+        assert_eq!(location_ids.saturating_similarities(), vec![0, 127, 127]);
+
+        let location_ids: LocationIds<u8> = "200 200\n100 100".parse().unwrap();
+        assert_eq!(
+            location_ids.checked_similarity_sum(),
+            Err(ScoreError::SimilaritySumOverflow { id: 100 })
+        );
+    }
+
+    #[test]
+    fn count_overflows() {
+        let input = "1 1\n".repeat(300);
+        let location_ids: LocationIds<u8> = input.parse().unwrap();
+        assert_eq!(
+            location_ids.checked_similarities().unwrap_err(),
+            ScoreError::CountOverflow { id: 1, count: 300 }
+        );
+        assert_eq!(location_ids.saturating_similarities()[0], 255);
+    }
+}
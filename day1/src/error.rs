@@ -0,0 +1,92 @@
+use std::fmt::Debug;
+
+use thiserror::Error;
+
+/// Why a line of location ids could not be read. Lines and columns are 1-indexed, columns count
+/// characters rather than bytes.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ParseError<E>
+where
+    E: Debug,
+{
+    #[error("line {line}, column {column}: expected {expected} columns but found {found}")]
+    MissingColumn {
+        line: usize,
+        column: usize,
+        expected: usize,
+        found: usize,
+    },
+    #[error("line {line}, column {column}: expected {expected} columns but found {found}")]
+    ExtraColumn {
+        line: usize,
+        column: usize,
+        expected: usize,
+        found: usize,
+    },
+    #[error("line {line}, column {column}: quoted field is never closed")]
+    UnterminatedQuote { line: usize, column: usize },
+    #[error("line {line}, column {column}: {token:?} is not a valid location id: {error:?}")]
+    InvalidId {
+        line: usize,
+        column: usize,
+        token: String,
+        error: E,
+    },
+}
+
+impl<E> ParseError<E>
+where
+    E: Debug,
+{
+    pub fn line(&self) -> usize {
+        match self {
+            ParseError::MissingColumn { line, .. }
+            | ParseError::ExtraColumn { line, .. }
+            | ParseError::UnterminatedQuote { line, .. }
+            | ParseError::InvalidId { line, .. } => *line,
+        }
+    }
+}
+
+/// A line that was skipped while parsing leniently, along with the reason it was skipped
+#[derive(Debug, PartialEq, Eq)]
+pub struct RejectedRow<E>
+where
+    E: Debug,
+{
+    pub row: String,
+    pub error: ParseError<E>,
+}
+
+/// Arithmetic that did not fit in the location id type while scoring
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ScoreError<T>
+where
+    T: Debug,
+{
+    #[error("the difference between {left:?} and {right:?} overflows")]
+    DifferenceOverflow { left: T, right: T },
+    #[error("{id:?} appears {count} times in the right list, which overflows")]
+    CountOverflow { id: T, count: usize },
+    #[error("the similarity of {id:?} ({id:?} * {count}) overflows")]
+    SimilarityOverflow { id: T, count: usize },
+    #[error("adding the difference between {left:?} and {right:?} overflows the sum")]
+    DifferenceSumOverflow { left: T, right: T },
+    #[error("adding the similarity of {id:?} overflows the sum")]
+    SimilaritySumOverflow { id: T },
+}
+
+/// Why [`crate::external::external_totals`] could not finish
+#[derive(Debug, Error)]
+pub enum ExternalError<E, T>
+where
+    E: Debug,
+    T: Debug,
+{
+    #[error("failed to read input or spill sorted runs: {0}")]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Parse(#[from] ParseError<E>),
+    #[error(transparent)]
+    Score(#[from] ScoreError<T>),
+}
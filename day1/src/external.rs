@@ -0,0 +1,387 @@
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    fmt::{Debug, Display},
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    iter::Peekable,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use tempfile::TempDir;
+
+use crate::{
+    checked::{checked_similarity, CheckedScore},
+    error::{ExternalError, ScoreError},
+    format::InputFormat,
+};
+
+/// Limits for [`external_totals`]
+#[derive(Debug, Clone)]
+pub struct ExternalConfig {
+    /// How many ids from each list are held in memory before they are sorted and spilled to disk
+    pub chunk_size: usize,
+    /// How many sorted runs are merged at once. Each run being merged holds one id in memory and
+    /// one open file.
+    pub merge_width: usize,
+    /// Where to spill sorted runs, defaulting to the system's temporary directory
+    pub temp_dir: Option<PathBuf>,
+    pub format: InputFormat,
+}
+
+impl Default for ExternalConfig {
+    fn default() -> Self {
+        ExternalConfig {
+            chunk_size: 1 << 20,
+            merge_width: 64,
+            temp_dir: None,
+            format: InputFormat::default(),
+        }
+    }
+}
+
+/// The part 1 and part 2 sums of a location list, as calculated by [`external_totals`]
+#[derive(Debug, PartialEq, Eq)]
+pub struct ExternalTotals<T> {
+    pub rows: usize,
+    pub difference_sum: T,
+    pub similarity_sum: T,
+}
+
+/// Calculates the sum of differences and the sum of similarities of a location list read from
+/// `reader`, without holding the whole list in memory.
+///
+/// Each list is sorted in chunks of `config.chunk_size` ids, which are spilled to temporary files
+/// and then merged back together, `config.merge_width` runs at a time. Both sums are found in a
+/// single pass over the merged lists. Blank lines are skipped, and arithmetic is checked as in
+/// [`crate::LocationIds::checked_difference_sum`].
+pub fn external_totals<R, T>(
+    mut reader: R,
+    config: &ExternalConfig,
+) -> Result<ExternalTotals<T>, ExternalError<T::Err, T>>
+where
+    R: BufRead,
+    T: CheckedScore + FromStr + Display,
+    <T as FromStr>::Err: Debug,
+{
+    let chunk_size = config.chunk_size.max(1);
+    let merge_width = config.merge_width.max(2);
+    let mut spill = Spill::new(config.temp_dir.as_deref())?;
+    let mut left_runs = Vec::new();
+    let mut right_runs = Vec::new();
+    let mut left: Vec<T> = Vec::with_capacity(chunk_size);
+    let mut right: Vec<T> = Vec::with_capacity(chunk_size);
+
+    let mut rows = 0;
+    let mut line = String::new();
+    let mut line_number = 0;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            break;
+        }
+        line_number += 1;
+        if !config.format.is_row(line_number, &line) {
+            continue;
+        }
+        let (l, r) = config
+            .format
+            .parse_row(line_number, line.trim_end_matches(['\r', '\n']))?;
+        left.push(l);
+        right.push(r);
+        rows += 1;
+        if left.len() == chunk_size {
+            left_runs.push(spill.write_run(&mut left)?);
+            right_runs.push(spill.write_run(&mut right)?);
+        }
+    }
+    if !left.is_empty() {
+        left_runs.push(spill.write_run(&mut left)?);
+        right_runs.push(spill.write_run(&mut right)?);
+    }
+    drop((left, right));
+
+    let left = spill.merge_all::<T>(left_runs, merge_width)?;
+    let right = spill.merge_all::<T>(right_runs, merge_width)?;
+    let (difference_sum, similarity_sum) = sums(left, right)?;
+    Ok(ExternalTotals {
+        rows,
+        difference_sum,
+        similarity_sum,
+    })
+}
+
+/// Walks both sorted lists together, one distinct id at a time.
+///
+/// The sum of differences between sorted lists is the area between their cumulative counts, so
+/// for each gap between adjacent ids it adds the gap times how many more left ids than right ids
+/// came before it (see [`crate::live::LiveLocationIds`]).
+fn sums<T, E>(left: Merge<T>, right: Merge<T>) -> Result<(T, T), ExternalError<E, T>>
+where
+    T: CheckedScore + FromStr + Display,
+    E: Debug,
+{
+    let zero = T::saturating_from_count(0);
+    let mut difference_sum = zero;
+    let mut similarity_sum = zero;
+    let mut balance = 0isize;
+    let mut prev: Option<T> = None;
+    let mut left = left.peekable();
+    let mut right = right.peekable();
+    loop {
+        let id = match (peek(&mut left)?, peek(&mut right)?) {
+            (Some(l), Some(r)) => l.min(r),
+            (Some(l), None) => l,
+            (None, Some(r)) => r,
+            (None, None) => break,
+        };
+        if let Some(prev) = prev {
+            let area = id.checked_sub(prev).and_then(|gap| {
+                T::checked_from_count(balance.unsigned_abs())
+                    .and_then(|balance| gap.checked_mul(balance))
+            });
+            let overflow = ScoreError::DifferenceOverflow {
+                left: prev,
+                right: id,
+            };
+            let sum_overflow = ScoreError::DifferenceSumOverflow {
+                left: prev,
+                right: id,
+            };
+            difference_sum = difference_sum
+                .checked_add(area.ok_or(overflow)?)
+                .ok_or(sum_overflow)?;
+        }
+        let left_count = take_all(&mut left, id)?;
+        let right_count = take_all(&mut right, id)?;
+        let both = left_count
+            .checked_mul(right_count)
+            .ok_or(ScoreError::CountOverflow {
+                id,
+                count: right_count,
+            })?;
+        similarity_sum = similarity_sum
+            .checked_add(checked_similarity(id, both)?)
+            .ok_or(ScoreError::SimilaritySumOverflow { id })?;
+        balance += left_count as isize - right_count as isize;
+        prev = Some(id);
+    }
+    Ok((difference_sum, similarity_sum))
+}
+
+fn peek<T>(ids: &mut Peekable<Merge<T>>) -> io::Result<Option<T>>
+where
+    T: Copy + Ord + FromStr,
+{
+    match ids.peek() {
+        Some(Ok(id)) => Ok(Some(*id)),
+        Some(Err(_)) => ids.next().transpose(),
+        None => Ok(None),
+    }
+}
+
+fn take_all<T>(ids: &mut Peekable<Merge<T>>, id: T) -> io::Result<usize>
+where
+    T: Copy + Ord + FromStr,
+{
+    let mut count = 0;
+    while peek(ids)? == Some(id) {
+        ids.next();
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// The temporary directory holding sorted runs, which is removed when dropped
+struct Spill {
+    dir: TempDir,
+    next_run: usize,
+}
+
+impl Spill {
+    fn new(parent: Option<&Path>) -> io::Result<Self> {
+        let mut builder = tempfile::Builder::new();
+        builder.prefix("day1-runs-");
+        let dir = match parent {
+            Some(parent) => builder.tempdir_in(parent)?,
+            None => builder.tempdir()?,
+        };
+        Ok(Spill { dir, next_run: 0 })
+    }
+
+    fn next_path(&mut self) -> PathBuf {
+        self.next_run += 1;
+        self.dir.path().join(format!("run-{}", self.next_run))
+    }
+
+    /// Sorts and writes out `ids`, leaving it empty
+    fn write_run<T>(&mut self, ids: &mut Vec<T>) -> io::Result<PathBuf>
+    where
+        T: Ord + Display,
+    {
+        ids.sort_unstable();
+        let path = self.next_path();
+        let mut file = BufWriter::new(File::create(&path)?);
+        for id in ids.drain(..) {
+            writeln!(file, "{id}")?;
+        }
+        file.flush()?;
+        Ok(path)
+    }
+
+    /// Merges runs `width` at a time until few enough are left to merge in one final pass
+    fn merge_all<T>(&mut self, mut runs: Vec<PathBuf>, width: usize) -> io::Result<Merge<T>>
+    where
+        T: Copy + Ord + FromStr + Display,
+    {
+        while runs.len() > width {
+            let rest = runs.split_off(width);
+            let path = self.next_path();
+            let mut file = BufWriter::new(File::create(&path)?);
+            for id in Merge::<T>::open(&runs)? {
+                writeln!(file, "{}", id?)?;
+            }
+            file.flush()?;
+            for run in runs {
+                std::fs::remove_file(run)?;
+            }
+            runs = rest;
+            runs.push(path);
+        }
+        Merge::open(&runs)
+    }
+}
+
+/// A k-way merge of sorted run files
+struct Merge<T> {
+    runs: Vec<std::io::Lines<BufReader<File>>>,
+    heap: BinaryHeap<Reverse<(T, usize)>>,
+}
+
+impl<T> Merge<T>
+where
+    T: Ord + FromStr,
+{
+    fn open(paths: &[PathBuf]) -> io::Result<Self> {
+        let mut merge = Merge {
+            runs: Vec::with_capacity(paths.len()),
+            heap: BinaryHeap::with_capacity(paths.len()),
+        };
+        for (idx, path) in paths.iter().enumerate() {
+            merge.runs.push(BufReader::new(File::open(path)?).lines());
+            merge.refill(idx)?;
+        }
+        Ok(merge)
+    }
+
+    fn refill(&mut self, run: usize) -> io::Result<()> {
+        if let Some(line) = self.runs[run].next() {
+            let id = line?.parse().map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidData, "spilled run was corrupted")
+            })?;
+            self.heap.push(Reverse((id, run)));
+        }
+        Ok(())
+    }
+}
+
+impl<T> Iterator for Merge<T>
+where
+    T: Ord + FromStr,
+{
+    type Item = io::Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse((id, run)) = self.heap.pop()?;
+        Some(self.refill(run).map(|_| id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{format::InputFormat, LocationIds};
+
+    const INPUT: &str = include_str!("input.txt");
+
+    #[test]
+    fn matches_in_memory() {
+        let location_ids: LocationIds<u64> = INPUT.parse().unwrap();
+        let expected = ExternalTotals {
+            rows: location_ids.left_list.len(),
+            difference_sum: location_ids.checked_difference_sum().unwrap(),
+            similarity_sum: location_ids.checked_similarity_sum().unwrap(),
+        };
+        for (chunk_size, merge_width) in [(1 << 20, 64), (100, 64), (7, 3), (1, 2)] {
+            let config = ExternalConfig {
+                chunk_size,
+                merge_width,
+                ..Default::default()
+            };
+            assert_eq!(
+                external_totals(INPUT.as_bytes(), &config).unwrap(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn negative_ids() {
+        let input = "-3 4\n4 -3\n2 5\n\n-1 -3\n";
+        let location_ids: LocationIds<i64> = input.replace("\n\n", "\n").parse().unwrap();
+        let config = ExternalConfig {
+            chunk_size: 2,
+            ..Default::default()
+        };
+        let totals = external_totals::<_, i64>(input.as_bytes(), &config).unwrap();
+        assert_eq!(totals.rows, 4);
+        assert_eq!(
+            totals.difference_sum,
+            location_ids.get_differences().into_iter().sum::<i64>()
+        );
+        assert_eq!(
+            totals.similarity_sum,
+            location_ids.get_similarities().into_iter().sum::<i64>()
+        );
+    }
+
+    #[test]
+    fn blank_lines_match_in_memory() {
+        for (input, format) in [
+            ("3 4\n\n4 3\n  \n2 5\n\n", InputFormat::default()),
+            (
+                "left,right\n\n3,4\n \n4,3\n2,5",
+                InputFormat::csv().with_header_rows(1),
+            ),
+        ] {
+            let location_ids: LocationIds<u32> = format.parse(input).unwrap();
+            assert_eq!(location_ids.left_list, vec![3, 4, 2]);
+            let config = ExternalConfig {
+                format,
+                ..Default::default()
+            };
+            let totals = external_totals::<_, u32>(input.as_bytes(), &config).unwrap();
+            assert_eq!(
+                (totals.rows, totals.difference_sum, totals.similarity_sum),
+                (
+                    3,
+                    location_ids.checked_difference_sum().unwrap(),
+                    location_ids.checked_similarity_sum().unwrap()
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn reports_parse_errors() {
+        let result = external_totals::<_, u8>("1 2\n3 x\n".as_bytes(), &Default::default());
+        assert!(matches!(
+            result,
+            Err(ExternalError::Parse(crate::error::ParseError::InvalidId {
+                line: 2,
+                ..
+            }))
+        ));
+    }
+}
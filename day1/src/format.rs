@@ -0,0 +1,291 @@
+use std::{fmt::Debug, str::FromStr};
+
+use crate::{
+    columns,
+    error::{ParseError, RejectedRow},
+    parse_id, LocationIds,
+};
+
+/// What separates the columns of a location list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delimiter {
+    /// Any run of whitespace, as in the puzzle input
+    Whitespace,
+    Char(char),
+}
+
+/// How a location list is laid out, for reading spreadsheet exports without preprocessing them.
+///
+/// The default reads the puzzle input: whitespace separated, no header, with the left ids in the
+/// first column and the right ids in the second.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputFormat {
+    pub delimiter: Delimiter,
+    /// Fields starting with this character run until the next one, and may contain the delimiter.
+    /// Two quotes in a row inside a quoted field stand for one literal quote. Only applies to
+    /// [`Delimiter::Char`].
+    pub quote: Option<char>,
+    /// How many lines to skip before the first row
+    pub header_rows: usize,
+    /// Which column (0-indexed) holds the left ids
+    pub left_column: usize,
+    /// Which column (0-indexed) holds the right ids
+    pub right_column: usize,
+}
+
+impl Default for InputFormat {
+    fn default() -> Self {
+        InputFormat {
+            delimiter: Delimiter::Whitespace,
+            quote: None,
+            header_rows: 0,
+            left_column: 0,
+            right_column: 1,
+        }
+    }
+}
+
+impl InputFormat {
+    /// Comma separated values with `"` quotes
+    pub fn csv() -> Self {
+        InputFormat {
+            delimiter: Delimiter::Char(','),
+            quote: Some('"'),
+            ..Default::default()
+        }
+    }
+
+    /// Tab separated values with `"` quotes
+    pub fn tsv() -> Self {
+        InputFormat {
+            delimiter: Delimiter::Char('\t'),
+            quote: Some('"'),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_delimiter(self, delimiter: char) -> Self {
+        InputFormat {
+            delimiter: Delimiter::Char(delimiter),
+            ..self
+        }
+    }
+
+    pub fn with_quote(self, quote: Option<char>) -> Self {
+        InputFormat { quote, ..self }
+    }
+
+    pub fn with_header_rows(self, header_rows: usize) -> Self {
+        InputFormat {
+            header_rows,
+            ..self
+        }
+    }
+
+    pub fn with_columns(self, left_column: usize, right_column: usize) -> Self {
+        InputFormat {
+            left_column,
+            right_column,
+            ..self
+        }
+    }
+
+    pub fn parse<T>(&self, s: &str) -> Result<LocationIds<T>, ParseError<T::Err>>
+    where
+        T: Clone + Ord + FromStr,
+        <T as FromStr>::Err: Debug,
+    {
+        let rows = self
+            .lines(s)
+            .map(|(line_number, line)| self.parse_row(line_number, line))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(LocationIds::from_rows(rows))
+    }
+
+    /// Parses like [`InputFormat::parse`], but skips any row that fails to parse instead of
+    /// failing outright. The skipped rows are returned alongside the ids that did parse.
+    pub fn parse_lenient<T>(&self, s: &str) -> (LocationIds<T>, Vec<RejectedRow<T::Err>>)
+    where
+        T: Clone + Ord + FromStr,
+        <T as FromStr>::Err: Debug,
+    {
+        let mut rejected = Vec::new();
+        let rows = self
+            .lines(s)
+            .filter_map(
+                |(line_number, line)| match self.parse_row(line_number, line) {
+                    Ok(row) => Some(row),
+                    Err(error) => {
+                        rejected.push(RejectedRow {
+                            row: line.to_string(),
+                            error,
+                        });
+                        None
+                    }
+                },
+            )
+            .collect::<Vec<_>>();
+        (LocationIds::from_rows(rows), rejected)
+    }
+
+    /// Whether the line at `line_number` (1-indexed) holds a row, rather than being part of the
+    /// header or blank. Blank lines are skipped wherever they are, so a gap between rows isn't an
+    /// error.
+    pub fn is_row(&self, line_number: usize, line: &str) -> bool {
+        line_number > self.header_rows && !line.trim().is_empty()
+    }
+
+    /// Each line holding a row, with its 1-indexed line number
+    fn lines<'a>(&'a self, s: &'a str) -> impl Iterator<Item = (usize, &'a str)> + 'a {
+        s.trim_end()
+            .lines()
+            .enumerate()
+            .map(|(idx, line)| (idx + 1, line))
+            .filter(|&(line_number, line)| self.is_row(line_number, line))
+    }
+
+    /// Reads the left and right id out of a single line
+    pub fn parse_row<T>(&self, line_number: usize, line: &str) -> Result<(T, T), ParseError<T::Err>>
+    where
+        T: FromStr,
+        <T as FromStr>::Err: Debug,
+    {
+        let fields = self.fields(line_number, line)?;
+        let expected = self.left_column.max(self.right_column) + 1;
+        if fields.len() < expected {
+            return Err(ParseError::MissingColumn {
+                line: line_number,
+                column: line.chars().count() + 1,
+                expected,
+                found: fields.len(),
+            });
+        }
+        let (left_at, left) = &fields[self.left_column];
+        let (right_at, right) = &fields[self.right_column];
+        Ok((
+            parse_id(line_number, *left_at, left)?,
+            parse_id(line_number, *right_at, right)?,
+        ))
+    }
+
+    /// Splits a line into its fields, each with the 1-indexed character column it starts at
+    fn fields<E>(
+        &self,
+        line_number: usize,
+        line: &str,
+    ) -> Result<Vec<(usize, String)>, ParseError<E>>
+    where
+        E: Debug,
+    {
+        let delimiter = match self.delimiter {
+            Delimiter::Whitespace => {
+                return Ok(columns(line)
+                    .map(|(column, token)| (column, token.to_string()))
+                    .collect())
+            }
+            Delimiter::Char(delimiter) => delimiter,
+        };
+
+        let mut fields = Vec::new();
+        let mut chars = line.chars().zip(1..).peekable();
+        loop {
+            // skip leading whitespace, unless the delimiter is whitespace itself
+            while chars
+                .next_if(|&(c, _)| c != delimiter && c.is_whitespace())
+                .is_some()
+            {}
+            let start = chars
+                .peek()
+                .map_or(line.chars().count() + 1, |&(_, column)| column);
+            let mut field = String::new();
+            if self.quote.is_some() && chars.next_if(|&(c, _)| Some(c) == self.quote).is_some() {
+                loop {
+                    match chars.next() {
+                        None => {
+                            return Err(ParseError::UnterminatedQuote {
+                                line: line_number,
+                                column: start,
+                            })
+                        }
+                        Some((c, _)) if Some(c) == self.quote => {
+                            if chars.next_if(|&(c, _)| Some(c) == self.quote).is_none() {
+                                break;
+                            }
+                            field.push(c);
+                        }
+                        Some((c, _)) => field.push(c),
+                    }
+                }
+                // anything between the closing quote and the delimiter is dropped
+                while chars.next_if(|&(c, _)| c != delimiter).is_some() {}
+            } else {
+                while let Some((c, _)) = chars.next_if(|&(c, _)| c != delimiter) {
+                    field.push(c);
+                }
+                field.truncate(field.trim_end().len());
+            }
+            fields.push((start, field));
+            if chars.next().is_none() {
+                break;
+            }
+        }
+        Ok(fields)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_with_header() {
+        let input = "\
+name,left,right
+\"alpha, inc\",3,4
+beta,  4 ,3
+\"say \"\"hi\"\"\",\"2\",5
+";
+        let format = InputFormat::csv().with_header_rows(1).with_columns(1, 2);
+        let location_ids: LocationIds<u8> = format.parse(input).unwrap();
+        assert_eq!(location_ids.left_list, vec![3, 4, 2]);
+        assert_eq!(location_ids.right_list, vec![4, 3, 5]);
+
+        let names: LocationIds<String> = format.clone().with_columns(0, 1).parse(input).unwrap();
+        assert_eq!(names.left_list, vec!["alpha, inc", "beta", "say \"hi\""]);
+    }
+
+    #[test]
+    fn tsv_errors() {
+        let format = InputFormat::tsv().with_columns(2, 0);
+        assert_eq!(
+            format.parse::<u8>("1\t2\t3\n4\t5").err(),
+            Some(ParseError::MissingColumn {
+                line: 2,
+                column: 4,
+                expected: 3,
+                found: 2
+            })
+        );
+        assert!(matches!(
+            format.parse::<u8>("1\t2\tx").err(),
+            Some(ParseError::InvalidId {
+                line: 1,
+                column: 5,
+                ..
+            })
+        ));
+        assert_eq!(
+            format.parse::<u8>("1\t\"2\t3").err(),
+            Some(ParseError::UnterminatedQuote { line: 1, column: 3 })
+        );
+
+        let (location_ids, rejected) = format.parse_lenient::<u8>("1\t2\t3\n4\t5\n6;7;8");
+        assert_eq!(location_ids.left_list, vec![3]);
+        assert_eq!(rejected.len(), 2);
+        let (location_ids, rejected) = format
+            .with_delimiter(';')
+            .parse_lenient::<u8>("1\t2\t3\n6;7;8");
+        assert_eq!(location_ids.left_list, vec![8]);
+        assert_eq!(rejected.len(), 1);
+    }
+}
@@ -1,8 +1,21 @@
-use std::{collections::BTreeMap, convert::Infallible, fmt::Debug, ops::{Mul, Sub}, str::FromStr};
+use std::{collections::BTreeMap, fmt::Debug, ops::{Bound, Mul, Sub}, str::FromStr};
+
+use checked::{checked_similarity, CheckedScore};
+use error::{ParseError, RejectedRow, ScoreError};
+use format::InputFormat;
+
+pub mod checked;
+pub mod error;
+pub mod external;
+pub mod format;
+pub mod live;
+pub mod metric;
+pub mod multi;
+pub mod report;
 
 pub struct LocationIds<T>
 where
-    T: Ord + Sub,
+    T: Ord,
 {
     pub left_list: Vec<T>,
     pub right_list: Vec<T>,
@@ -10,6 +23,35 @@ where
     sorted_right: BTreeMap<T, usize>,
 }
 
+impl<T> LocationIds<T>
+where
+    T: Ord,
+{
+    /// Pairs the smallest left id with the smallest right id, the second smallest with the second
+    /// smallest, and so on
+    pub fn get_pairings(&self) -> Vec<(&T, &T)> {
+        sorted_pairings(&self.sorted_left, &self.sorted_right).collect()
+    }
+
+    /// Each id in the left list, in order, alongside how many times it appears in the right list
+    pub fn get_occurrences(&self) -> Vec<(&T, usize)> {
+        self.left_list
+            .iter()
+            .map(|num| (num, self.right_count(num)))
+            .collect()
+    }
+
+    /// How many times `id` appears in the left list
+    pub fn left_count(&self, id: &T) -> usize {
+        self.sorted_left.get(id).copied().unwrap_or_default()
+    }
+
+    /// How many times `id` appears in the right list
+    pub fn right_count(&self, id: &T) -> usize {
+        self.sorted_right.get(id).copied().unwrap_or_default()
+    }
+}
+
 // I tried to make this generic to handle multiple types of LocationId<T>, but I think this may have
 // been a YAGNI mistake
 impl<T> LocationIds<T>
@@ -19,67 +61,308 @@ where
     T: TryFrom<usize>
 {
     pub fn get_differences(&self) -> Vec<T> {
-        self.sorted_left
+        self.get_pairings()
+            .into_iter()
+            .map(|(&left, &right)| difference(left, right))
+            .collect()
+    }
+
+    pub fn get_similarities(&self) -> Vec<T> {
+        self.get_occurrences()
+            .into_iter()
+            .map(|(&num, count)| num * count.try_into().unwrap_or_default())
+            .collect()
+    }
+}
+
+impl<T> LocationIds<T>
+where
+    T: CheckedScore + Default,
+{
+    /// Like [`LocationIds::get_occurrences`], but counts every right id within `tolerance` of the
+    /// left id rather than only exact matches
+    pub fn get_occurrences_within(&self, tolerance: T) -> Vec<(&T, usize)> {
+        // How many right ids are <= each id, so any range can be counted with two lookups
+        let mut running = 0;
+        let cumulative = self
+            .sorted_right
             .iter()
-            .flat_map(|(&num, &count)| std::iter::repeat(num).take(count))
-            .zip(
-                self.sorted_right
-                    .iter()
-                    .flat_map(|(&num, &count)| std::iter::repeat(num).take(count)),
-            )
-            .map(|(left, right)| {
-                if right > left {
-                    right - left
-                } else {
-                    left - right                }
+            .map(|(&id, &count)| {
+                running += count;
+                (id, running)
+            })
+            .collect::<BTreeMap<T, usize>>();
+        let count_up_to = |bound: Bound<T>| {
+            cumulative
+                .range((Bound::Unbounded, bound))
+                .next_back()
+                .map_or(0, |(_, &count)| count)
+        };
+
+        self.left_list
+            .iter()
+            .map(|num| {
+                if tolerance < T::default() {
+                    return (num, 0);
+                }
+                let below = num
+                    .checked_sub(tolerance)
+                    .map_or(0, |low| count_up_to(Bound::Excluded(low)));
+                let high = num.checked_add(tolerance).map_or(Bound::Unbounded, Bound::Included);
+                (num, count_up_to(high) - below)
             })
             .collect()
     }
 
-    pub fn get_similarities(&self) -> Vec<T> {
-        self.left_list.iter().map(|&num| {
-            let count = *self.sorted_right.get(&num).unwrap_or(&0);
-            num * count.try_into().unwrap_or_default()
-        }).collect()
+    /// Like [`LocationIds::checked_similarities`], but counts every right id within `tolerance`
+    /// of the left id rather than only exact matches
+    pub fn get_similarities_within(&self, tolerance: T) -> Result<Vec<T>, ScoreError<T>> {
+        self.get_occurrences_within(tolerance)
+            .into_iter()
+            .map(|(&num, count)| checked_similarity(num, count))
+            .collect()
+    }
+}
+
+fn sorted_pairings<'a, T>(
+    left: &'a BTreeMap<T, usize>,
+    right: &'a BTreeMap<T, usize>,
+) -> impl Iterator<Item = (&'a T, &'a T)> {
+    left.iter()
+        .flat_map(|(num, &count)| std::iter::repeat_n(num, count))
+        .zip(
+            right
+                .iter()
+                .flat_map(|(num, &count)| std::iter::repeat_n(num, count)),
+        )
+}
+
+fn difference<T>(left: T, right: T) -> T
+where
+    T: Sub<Output = T> + Ord,
+{
+    if right > left {
+        right - left
+    } else {
+        left - right
+    }
+}
+
+/// Takes one off the count for `key`, dropping it once none are left. Returns false if there
+/// were none to begin with.
+pub(crate) fn take_one<K: Ord>(counts: &mut BTreeMap<K, usize>, key: &K) -> bool {
+    match counts.get_mut(key) {
+        Some(count) if *count > 1 => *count -= 1,
+        Some(_) => {
+            counts.remove(key);
+        }
+        None => return false,
+    }
+    true
+}
+
+/// Yields each whitespace separated token in `line` along with its 1-indexed character column
+fn columns(line: &str) -> impl Iterator<Item = (usize, &str)> {
+    line.split_whitespace().map(move |token| {
+        let offset = token.as_ptr() as usize - line.as_ptr() as usize;
+        (line[..offset].chars().count() + 1, token)
+    })
+}
+
+fn parse_id<T>(line_number: usize, column: usize, token: &str) -> Result<T, ParseError<T::Err>>
+where
+    T: FromStr,
+    <T as FromStr>::Err: Debug,
+{
+    token.parse().map_err(|error| ParseError::InvalidId {
+        line: line_number,
+        column,
+        token: token.to_string(),
+        error,
+    })
+}
+
+impl<T> LocationIds<T>
+where
+    T: Clone,
+    T: Ord,
+{
+    pub fn new() -> Self {
+        LocationIds {
+            left_list: Vec::new(),
+            right_list: Vec::new(),
+            sorted_left: BTreeMap::new(),
+            sorted_right: BTreeMap::new(),
+        }
+    }
+
+    fn from_rows(rows: impl IntoIterator<Item = (T, T)>) -> Self {
+        let mut location_ids = Self::new();
+        for (left, right) in rows {
+            location_ids.push(left, right);
+        }
+        location_ids
+    }
+
+    pub fn push(&mut self, left: T, right: T) {
+        *self.sorted_left.entry(left.clone()).or_insert(0usize) += 1;
+        *self.sorted_right.entry(right.clone()).or_insert(0usize) += 1;
+        self.left_list.push(left);
+        self.right_list.push(right);
+    }
+
+    /// Removes the first row holding exactly `left` and `right`, returning false if there is no
+    /// such row
+    pub fn remove(&mut self, left: &T, right: &T) -> bool {
+        let Some(idx) = self
+            .left_list
+            .iter()
+            .zip(self.right_list.iter())
+            .position(|(l, r)| l == left && r == right)
+        else {
+            return false;
+        };
+        self.left_list.remove(idx);
+        self.right_list.remove(idx);
+        take_one(&mut self.sorted_left, left);
+        take_one(&mut self.sorted_right, right);
+        true
+    }
+
+    /// Parses like `from_str`, but skips any line that fails to parse instead of failing
+    /// outright. The skipped lines are returned alongside the ids that did parse.
+    pub fn from_str_lenient(s: &str) -> (Self, Vec<RejectedRow<T::Err>>)
+    where
+        T: FromStr,
+        <T as FromStr>::Err: Debug,
+    {
+        InputFormat::default().parse_lenient(s)
     }
 }
 
 impl<T> FromStr for LocationIds<T>
 where
     T: Clone,
-    T: Ord + Sub,
+    T: Ord,
     T: FromStr,
     <T as FromStr>::Err: Debug,
 {
-    type Err = Infallible;
+    type Err = ParseError<<T as FromStr>::Err>;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut left_list = Vec::new();
-        let mut right_list = Vec::new();
-        let mut sorted_left = BTreeMap::new();
-        let mut sorted_right = BTreeMap::new();
-        s.trim_end().lines().for_each(|line| {
-            let mut parts = line.split_whitespace().map(str::parse);
-
-            let left: T = parts
-                .next()
-                .unwrap_or_else(|| panic!("All lines must have a left side, but {} does not", line))
-                .expect("Left must be a valid number");
-            let right: T = parts
-                .next()
-                .unwrap_or_else(|| panic!("All lines must have a right side, but {} does not", line))
-                .expect("Right must be a valid number");
-
-            *sorted_left.entry(left.clone()).or_insert(0usize) += 1;
-            *sorted_right.entry(right.clone()).or_insert(0usize) += 1;
-            left_list.push(left);
-            right_list.push(right);
-        });
-        Ok(LocationIds {
-            left_list,
-            right_list,
-            sorted_left,
-            sorted_right,
-        })
+        InputFormat::default().parse(s)
+    }
+}
+
+impl<T> Default for LocationIds<T>
+where
+    T: Clone,
+    T: Ord,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::IntErrorKind;
+
+    use super::*;
+
+    #[test]
+    fn missing_right_column() {
+        let result = "3   4\n4\n".parse::<LocationIds<u8>>();
+        assert_eq!(
+            result.err(),
+            Some(ParseError::MissingColumn {
+                line: 2,
+                column: 2,
+                expected: 2,
+                found: 1
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_id() {
+        let Err(ParseError::InvalidId {
+            line,
+            column,
+            token,
+            error,
+        }) = "3   4\n4   300".parse::<LocationIds<u8>>()
+        else {
+            panic!("300 must not fit in a u8")
+        };
+        assert_eq!((line, column, token.as_str()), (2, 5, "300"));
+        assert_eq!(error.kind(), &IntErrorKind::PosOverflow);
+    }
+
+    #[test]
+    fn lenient_skips_bad_rows() {
+        let (location_ids, rejected) =
+            LocationIds::<u8>::from_str_lenient("3   4\nx   3\n2   5\n1");
+        assert_eq!(location_ids.left_list, vec![3, 2]);
+        assert_eq!(location_ids.right_list, vec![4, 5]);
+        assert_eq!(
+            rejected
+                .iter()
+                .map(|r| (r.error.line(), r.row.as_str()))
+                .collect::<Vec<_>>(),
+            vec![(2, "x   3"), (4, "1")]
+        );
+    }
+
+    #[test]
+    fn string_ids() {
+        let location_ids: LocationIds<String> = "b a\na c\nc a".parse().unwrap();
+        let occurrences = location_ids.get_occurrences();
+        assert_eq!(
+            occurrences
+                .into_iter()
+                .map(|(id, count)| (id.as_str(), count))
+                .collect::<Vec<_>>(),
+            vec![("b", 0), ("a", 2), ("c", 1)]
+        );
+        let pairings = location_ids.get_pairings();
+        assert_eq!(
+            pairings
+                .into_iter()
+                .map(|(left, right)| (left.as_str(), right.as_str()))
+                .collect::<Vec<_>>(),
+            vec![("a", "a"), ("b", "a"), ("c", "c")]
+        );
+    }
+
+    #[test]
+    fn similarities_within() {
+        let input = format!("{}\n255 254\n0   0", include_str!("test_input.txt").trim_end());
+        let location_ids: LocationIds<u8> = input.parse().unwrap();
+        assert_eq!(
+            location_ids.get_similarities_within(0),
+            Ok(location_ids.get_similarities())
+        );
+        let occurrences = location_ids
+            .get_occurrences_within(1)
+            .into_iter()
+            .map(|(_, count)| count)
+            .collect::<Vec<_>>();
+        assert_eq!(occurrences, vec![4, 5, 3, 1, 4, 4, 1, 1]);
+        assert_eq!(location_ids.get_similarities_within(1).unwrap()[2], 6);
+        let location_ids: LocationIds<u8> = "200 200\n0   201".parse().unwrap();
+        assert_eq!(
+            location_ids.get_similarities_within(1),
+            Err(ScoreError::SimilarityOverflow { id: 200, count: 2 })
+        );
+
+        let location_ids: LocationIds<i32> = "0 -3\n5 3\n-5 100".parse().unwrap();
+        let occurrences = location_ids
+            .get_occurrences_within(3)
+            .into_iter()
+            .map(|(_, count)| count)
+            .collect::<Vec<_>>();
+        assert_eq!(occurrences, vec![2, 1, 1]);
+        assert_eq!(location_ids.get_similarities_within(-1), Ok(vec![0, 0, 0]));
     }
 }
@@ -0,0 +1,309 @@
+use std::{
+    collections::BTreeMap,
+    ops::{Add, Mul, Sub},
+};
+
+use crate::{take_one, LocationIds};
+
+/// Like [`LocationIds`], but keeps the sums of its differences and similarities up to date as
+/// pairs are pushed and removed, instead of recalculating them from scratch.
+///
+/// The sum of differences between the sorted lists is the area between their cumulative counts:
+/// for every pair of adjacent ids `a < b` seen in either list, it adds `(b - a)` times how many more
+/// left ids than right ids are `<= a`. `balance` holds that running count for each id. Pushing or
+/// removing a pair only moves the count for ids between its left and right side, so only that
+/// range is revisited. That makes each update cost one step for every distinct id between the
+/// pair's left and right side, on top of the `O(log n)` map updates, which is cheap when pairs are
+/// close together but approaches a rescan of the distinct ids when they are far apart.
+///
+/// The order pairs were pushed in isn't kept, only how many times each pair appears, so removing
+/// one never has to search the rows for it.
+pub struct LiveLocationIds<T>
+where
+    T: Ord,
+{
+    sorted_left: BTreeMap<T, usize>,
+    sorted_right: BTreeMap<T, usize>,
+    /// How many rows hold each `(left, right)` pair
+    rows: BTreeMap<(T, T), usize>,
+    balance: BTreeMap<T, isize>,
+    difference_sum: T,
+    similarity_sum: T,
+}
+
+impl<T> LiveLocationIds<T>
+where
+    T: Sub<Output = T> + Add<Output = T> + Ord + Copy + Default,
+    T: Mul<Output = T>,
+    T: TryFrom<usize>,
+{
+    pub fn new() -> Self {
+        LiveLocationIds {
+            sorted_left: BTreeMap::new(),
+            sorted_right: BTreeMap::new(),
+            rows: BTreeMap::new(),
+            balance: BTreeMap::new(),
+            difference_sum: T::default(),
+            similarity_sum: T::default(),
+        }
+    }
+
+    /// How many times `id` appears in the left list
+    pub fn left_count(&self, id: &T) -> usize {
+        self.sorted_left.get(id).copied().unwrap_or_default()
+    }
+
+    /// How many times `id` appears in the right list
+    pub fn right_count(&self, id: &T) -> usize {
+        self.sorted_right.get(id).copied().unwrap_or_default()
+    }
+
+    /// The pairs as a [`LocationIds`]. Since the order they were pushed in isn't kept, its rows
+    /// are sorted by left id and then right id.
+    pub fn to_location_ids(&self) -> LocationIds<T> {
+        let mut ids = LocationIds::new();
+        for (&(left, right), &count) in &self.rows {
+            for _ in 0..count {
+                ids.push(left, right);
+            }
+        }
+        ids
+    }
+
+    /// The sum of [`LocationIds::get_differences`]
+    pub fn difference_sum(&self) -> T {
+        self.difference_sum
+    }
+
+    /// The sum of [`LocationIds::get_similarities`]
+    pub fn similarity_sum(&self) -> T {
+        self.similarity_sum
+    }
+
+    pub fn push(&mut self, left: T, right: T) {
+        let right_count = self.right_count(&left);
+        let left_count = self.left_count(&right) + usize::from(left == right);
+        self.similarity_sum =
+            self.similarity_sum + left * count(right_count) + right * count(left_count);
+
+        for id in [left, right] {
+            if !self.balance.contains_key(&id) {
+                let before = self.balance.range(..id).next_back().map_or(0, |(_, &b)| b);
+                self.balance.insert(id, before);
+            }
+        }
+        self.rebalance(left, right, 1);
+        *self.sorted_left.entry(left).or_insert(0) += 1;
+        *self.sorted_right.entry(right).or_insert(0) += 1;
+        *self.rows.entry((left, right)).or_insert(0) += 1;
+    }
+
+    /// Removes a row holding exactly `left` and `right`, returning false if there is no such row
+    pub fn remove(&mut self, left: &T, right: &T) -> bool {
+        let (left, right) = (*left, *right);
+        if !take_one(&mut self.rows, &(left, right)) {
+            return false;
+        }
+        take_one(&mut self.sorted_left, &left);
+        take_one(&mut self.sorted_right, &right);
+        let right_count = self.right_count(&left) + usize::from(left == right);
+        let left_count = self.left_count(&right);
+        self.similarity_sum =
+            self.similarity_sum - left * count(right_count) - right * count(left_count);
+
+        self.rebalance(left, right, -1);
+        for id in [left, right] {
+            if self.left_count(&id) == 0 && self.right_count(&id) == 0 {
+                self.balance.remove(&id);
+            }
+        }
+        true
+    }
+
+    /// Adds `step` left ids (or removes them, if negative) to the balance of every id from `left`
+    /// up to `right`, and subtracts it going the other way.
+    fn rebalance(&mut self, left: T, right: T, step: isize) {
+        let (low, high, step) = if left < right {
+            (left, right, step)
+        } else {
+            (right, left, -step)
+        };
+        // the id whose gap to the next id hasn't been measured yet, and whether its balance grew
+        let mut pending: Option<(T, bool)> = None;
+        for (&id, balance) in self.balance.range_mut(low..=high) {
+            if let Some((prev, grew)) = pending.take() {
+                let gap = id - prev;
+                self.difference_sum = if grew {
+                    self.difference_sum + gap
+                } else {
+                    self.difference_sum - gap
+                };
+            }
+            if id < high {
+                let before = balance.unsigned_abs();
+                *balance += step;
+                pending = Some((id, balance.unsigned_abs() > before));
+            }
+        }
+    }
+}
+
+impl<T> Default for LiveLocationIds<T>
+where
+    T: Sub<Output = T> + Add<Output = T> + Ord + Copy + Default,
+    T: Mul<Output = T>,
+    T: TryFrom<usize>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> From<LocationIds<T>> for LiveLocationIds<T>
+where
+    T: Sub<Output = T> + Add<Output = T> + Ord + Copy + Default,
+    T: Mul<Output = T>,
+    T: TryFrom<usize>,
+{
+    fn from(ids: LocationIds<T>) -> Self {
+        let difference_sum = ids
+            .get_differences()
+            .into_iter()
+            .fold(T::default(), |a, b| a + b);
+        let similarity_sum = ids
+            .get_similarities()
+            .into_iter()
+            .fold(T::default(), |a, b| a + b);
+
+        let mut balance = BTreeMap::new();
+        let mut running = 0isize;
+        let mut left = ids.sorted_left.iter().peekable();
+        let mut right = ids.sorted_right.iter().peekable();
+        loop {
+            let id = match (left.peek(), right.peek()) {
+                (Some(&(&l, _)), Some(&(&r, _))) => l.min(r),
+                (Some(&(&l, _)), None) => l,
+                (None, Some(&(&r, _))) => r,
+                (None, None) => break,
+            };
+            if let Some((_, &count)) = left.next_if(|&(&l, _)| l == id) {
+                running += count as isize;
+            }
+            if let Some((_, &count)) = right.next_if(|&(&r, _)| r == id) {
+                running -= count as isize;
+            }
+            balance.insert(id, running);
+        }
+
+        let mut rows = BTreeMap::new();
+        for row in ids.left_list.into_iter().zip(ids.right_list) {
+            *rows.entry(row).or_insert(0) += 1;
+        }
+
+        LiveLocationIds {
+            sorted_left: ids.sorted_left,
+            sorted_right: ids.sorted_right,
+            rows,
+            balance,
+            difference_sum,
+            similarity_sum,
+        }
+    }
+}
+
+fn count<T>(count: usize) -> T
+where
+    T: TryFrom<usize> + Default,
+{
+    count.try_into().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = include_str!("test_input.txt");
+
+    fn assert_sums(live: &LiveLocationIds<i64>) {
+        let ids = live.to_location_ids();
+        assert_eq!(
+            live.difference_sum(),
+            ids.get_differences().into_iter().sum::<i64>()
+        );
+        assert_eq!(
+            live.similarity_sum(),
+            ids.get_similarities().into_iter().sum::<i64>()
+        );
+    }
+
+    #[test]
+    fn matches_test_input() {
+        let mut live = LiveLocationIds::new();
+        for line in INPUT.lines() {
+            let (left, right) = line.split_once("   ").unwrap();
+            live.push(left.parse().unwrap(), right.parse().unwrap());
+            assert_sums(&live);
+        }
+        assert_eq!((live.difference_sum(), live.similarity_sum()), (11, 31));
+
+        let parsed: LiveLocationIds<i64> = INPUT.parse::<LocationIds<i64>>().unwrap().into();
+        assert_eq!((parsed.difference_sum(), parsed.similarity_sum()), (11, 31));
+    }
+
+    #[test]
+    fn push_and_remove() {
+        let mut live: LiveLocationIds<i64> = INPUT.parse::<LocationIds<i64>>().unwrap().into();
+        let pairs = (0..60).map(|n: i64| ((n * 7) % 13 - 4, (n * 5) % 11));
+        for (left, right) in pairs.clone() {
+            live.push(left, right);
+            assert_sums(&live);
+        }
+        assert!(!live.remove(&100, &100));
+        for (left, right) in pairs.rev().step_by(2) {
+            assert!(live.remove(&left, &right));
+            assert_sums(&live);
+        }
+        for (left, right) in [(3, 4), (4, 3), (2, 5)] {
+            assert!(live.remove(&left, &right));
+            assert_sums(&live);
+        }
+    }
+
+    #[test]
+    fn wide_pairs() {
+        // pairs scattered across the whole range of ids, so every update rebalances a wide range
+        let pairs = (0..2_000).map(|n: i64| ((n * 7_919) % 10_007, (n * 104_729 + 13) % 10_009));
+        let mut live = LiveLocationIds::new();
+        for (idx, (left, right)) in pairs.clone().enumerate() {
+            live.push(left, right);
+            if idx % 100 == 0 {
+                assert_sums(&live);
+            }
+        }
+        assert_sums(&live);
+        for (idx, (left, right)) in pairs.step_by(3).enumerate() {
+            assert!(live.remove(&left, &right));
+            if idx % 50 == 0 {
+                assert_sums(&live);
+            }
+        }
+        assert_sums(&live);
+    }
+
+    #[test]
+    fn many_rows() {
+        let mut live = LiveLocationIds::<i64>::new();
+        for id in 0..20_000 {
+            live.push(id, id + 1);
+        }
+        assert_eq!(
+            (live.difference_sum(), live.similarity_sum()),
+            (20_000, 19_999 * 10_000)
+        );
+        for id in 0..20_000 {
+            assert!(live.remove(&id, &(id + 1)));
+        }
+        assert_eq!((live.difference_sum(), live.similarity_sum()), (0, 0));
+        assert!(live.to_location_ids().left_list.is_empty());
+    }
+}
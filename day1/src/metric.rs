@@ -0,0 +1,270 @@
+use std::ops::{Add, Mul, Sub};
+
+use crate::{difference, LocationIds};
+
+/// How far apart two location ids are. The default scoring uses [`Absolute`].
+pub trait DistanceMetric<T> {
+    type Cost;
+
+    fn cost(&self, left: &T, right: &T) -> Self::Cost;
+
+    /// Whether pairing the sorted lists (as [`LocationIds::get_pairings`] does) is guaranteed to
+    /// give the lowest total cost. This holds when the cost is a convex function of
+    /// `|left - right|`, so only return true for such metrics. Other metrics fall back to solving
+    /// the full assignment problem.
+    fn sorted_is_optimal(&self) -> bool {
+        false
+    }
+}
+
+/// The absolute difference between ids
+pub struct Absolute;
+
+/// The square of the difference between ids, which punishes a few large gaps more than many small
+/// ones
+pub struct Squared;
+
+/// The absolute difference between ids, capped at the given threshold so that any two ids further
+/// apart than it cost the same
+pub struct Thresholded<T>(pub T);
+
+/// Any cost function, e.g. `Custom(|a: &u32, b: &u32| a.abs_diff(*b).min(10))`
+pub struct Custom<F>(pub F);
+
+impl<T> DistanceMetric<T> for Absolute
+where
+    T: Sub<Output = T> + Ord + Copy,
+{
+    type Cost = T;
+
+    fn cost(&self, &left: &T, &right: &T) -> T {
+        difference(left, right)
+    }
+
+    fn sorted_is_optimal(&self) -> bool {
+        true
+    }
+}
+
+impl<T> DistanceMetric<T> for Squared
+where
+    T: Sub<Output = T> + Mul<Output = T> + Ord + Copy,
+{
+    type Cost = T;
+
+    fn cost(&self, &left: &T, &right: &T) -> T {
+        let difference = difference(left, right);
+        difference * difference
+    }
+
+    fn sorted_is_optimal(&self) -> bool {
+        true
+    }
+}
+
+impl<T> DistanceMetric<T> for Thresholded<T>
+where
+    T: Sub<Output = T> + Ord + Copy,
+{
+    type Cost = T;
+
+    fn cost(&self, &left: &T, &right: &T) -> T {
+        difference(left, right).min(self.0)
+    }
+}
+
+impl<T, C, F> DistanceMetric<T> for Custom<F>
+where
+    F: Fn(&T, &T) -> C,
+{
+    type Cost = C;
+
+    fn cost(&self, left: &T, right: &T) -> C {
+        (self.0)(left, right)
+    }
+}
+
+/// Which left id was paired with which right id, sorted by left id, and what that pairing cost
+#[derive(Debug, PartialEq)]
+pub struct Matching<'a, T, C> {
+    pub pairs: Vec<(&'a T, &'a T)>,
+    pub total: C,
+}
+
+impl<T> LocationIds<T>
+where
+    T: Ord,
+{
+    /// Pairs every left id with a right id so that the total cost under `metric` is as low as
+    /// possible.
+    ///
+    /// Metrics whose [`DistanceMetric::sorted_is_optimal`] is false are solved with the Hungarian
+    /// algorithm, which is O(n^3) in the length of the lists.
+    pub fn match_with<M>(&self, metric: &M) -> Matching<'_, T, M::Cost>
+    where
+        M: DistanceMetric<T>,
+        M::Cost: Copy + PartialOrd + Add<Output = M::Cost> + Sub<Output = M::Cost> + Default,
+    {
+        let mut pairs = if metric.sorted_is_optimal() {
+            self.get_pairings()
+        } else {
+            let costs = self
+                .left_list
+                .iter()
+                .map(|left| {
+                    self.right_list
+                        .iter()
+                        .map(|right| metric.cost(left, right))
+                        .collect()
+                })
+                .collect::<Vec<Vec<_>>>();
+            min_cost_assignment(&costs)
+                .into_iter()
+                .enumerate()
+                .map(|(row, col)| (&self.left_list[row], &self.right_list[col]))
+                .collect()
+        };
+        pairs.sort();
+        let total = pairs.iter().fold(M::Cost::default(), |sum, (left, right)| {
+            sum + metric.cost(left, right)
+        });
+        Matching { pairs, total }
+    }
+}
+
+/// Solves the assignment problem for a square cost matrix, returning the column assigned to each
+/// row.
+///
+/// This is the usual O(n^3) Hungarian algorithm, except that the column potentials are stored
+/// negated. That keeps every intermediate value non-negative, so it works for unsigned costs too.
+fn min_cost_assignment<C>(costs: &[Vec<C>]) -> Vec<usize>
+where
+    C: Copy + PartialOrd + Add<Output = C> + Sub<Output = C> + Default,
+{
+    let n = costs.len();
+    // Everything is 1-indexed, index 0 is a sentinel column holding the row being added
+    let mut row_potential = vec![C::default(); n + 1];
+    let mut col_potential = vec![C::default(); n + 1];
+    let mut row_for_col = vec![0usize; n + 1];
+    let mut way = vec![0usize; n + 1];
+
+    for row in 1..=n {
+        row_for_col[0] = row;
+        let mut col = 0;
+        let mut min_slack: Vec<Option<C>> = vec![None; n + 1];
+        let mut used = vec![false; n + 1];
+        loop {
+            used[col] = true;
+            let current_row = row_for_col[col];
+            let mut delta: Option<C> = None;
+            let mut next_col = 0;
+            for j in (1..=n).filter(|&j| !used[j]) {
+                let slack =
+                    costs[current_row - 1][j - 1] + col_potential[j] - row_potential[current_row];
+                if min_slack[j].is_none_or(|min| slack < min) {
+                    min_slack[j] = Some(slack);
+                    way[j] = col;
+                }
+                if delta.is_none_or(|delta| min_slack[j].is_some_and(|min| min < delta)) {
+                    delta = min_slack[j];
+                    next_col = j;
+                }
+            }
+            let delta = delta.expect("there is always an unused column while a row is unmatched");
+            for j in 0..=n {
+                if used[j] {
+                    row_potential[row_for_col[j]] = row_potential[row_for_col[j]] + delta;
+                    col_potential[j] = col_potential[j] + delta;
+                } else if let Some(slack) = min_slack[j].as_mut() {
+                    *slack = *slack - delta;
+                }
+            }
+            col = next_col;
+            if row_for_col[col] == 0 {
+                break;
+            }
+        }
+        while col != 0 {
+            let prev = way[col];
+            row_for_col[col] = row_for_col[prev];
+            col = prev;
+        }
+    }
+
+    let mut col_for_row = vec![0; n];
+    for col in 1..=n {
+        col_for_row[row_for_col[col] - 1] = col - 1;
+    }
+    col_for_row
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = include_str!("test_input.txt");
+
+    #[test]
+    fn absolute_matches_differences() {
+        let location_ids: LocationIds<u32> = INPUT.parse().unwrap();
+        let matching = location_ids.match_with(&Absolute);
+        assert_eq!(matching.total, 11);
+        assert_eq!(
+            matching.total,
+            location_ids
+                .match_with(&Custom(|a: &u32, b: &u32| a.abs_diff(*b)))
+                .total
+        );
+        assert_eq!(location_ids.match_with(&Squared).total, 4 + 1 + 1 + 4 + 25);
+    }
+
+    #[test]
+    fn thresholded_beats_sorted() {
+        let location_ids: LocationIds<u32> = "0 5\n5 10".parse().unwrap();
+        let matching = location_ids.match_with(&Thresholded(3));
+        assert_eq!(matching.total, 3);
+        assert_eq!(matching.pairs, vec![(&0, &10), (&5, &5)]);
+    }
+
+    #[test]
+    fn assignment_is_optimal() {
+        fn permutations(n: usize) -> Vec<Vec<usize>> {
+            if n == 0 {
+                return vec![vec![]];
+            }
+            permutations(n - 1)
+                .into_iter()
+                .flat_map(|perm| {
+                    (0..n).map(move |at| {
+                        let mut perm = perm.clone();
+                        perm.insert(at, n - 1);
+                        perm
+                    })
+                })
+                .collect()
+        }
+        for seed in 0..20u64 {
+            let n = 1 + (seed % 6) as usize;
+            let costs = (0..n)
+                .map(|i| {
+                    (0..n)
+                        .map(|j| (seed * 31 + i as u64 * 17 + j as u64 * 7) * 2654435761 % 97)
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            let total = |assignment: &[usize]| -> u64 {
+                assignment
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &j)| costs[i][j])
+                    .sum()
+            };
+            let best = permutations(n)
+                .iter()
+                .map(|perm| total(perm))
+                .min()
+                .unwrap();
+            assert_eq!(total(&min_cost_assignment(&costs)), best, "{costs:?}");
+        }
+    }
+}
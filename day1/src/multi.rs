@@ -0,0 +1,215 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    ops::{Add, Mul, Sub},
+    str::FromStr,
+};
+
+use crate::{
+    checked::{checked_similarity, zero, CheckedScore},
+    columns, difference,
+    error::{ParseError, ScoreError},
+    parse_id, sorted_pairings,
+};
+
+/// Like [`crate::LocationIds`], but for any number of lists side by side rather than just a left
+/// and a right
+pub struct MultiLocationIds<T>
+where
+    T: Ord,
+{
+    pub lists: Vec<Vec<T>>,
+    sorted: Vec<BTreeMap<T, usize>>,
+}
+
+impl<T> MultiLocationIds<T>
+where
+    T: Ord,
+{
+    /// How many lists (columns) there are
+    pub fn width(&self) -> usize {
+        self.lists.len()
+    }
+
+    /// How many times `id` appears in the list at `column`
+    pub fn count(&self, column: usize, id: &T) -> usize {
+        self.sorted[column].get(id).copied().unwrap_or_default()
+    }
+
+    /// The sorted pairings between the lists at columns `a` and `b`, as in
+    /// [`crate::LocationIds::get_pairings`]
+    pub fn get_pairings(&self, a: usize, b: usize) -> Vec<(&T, &T)> {
+        sorted_pairings(&self.sorted[a], &self.sorted[b]).collect()
+    }
+}
+
+impl<T> MultiLocationIds<T>
+where
+    T: Sub<Output = T> + Add<Output = T> + Ord + Copy + Default,
+    T: Mul<Output = T>,
+    T: TryFrom<usize>,
+{
+    /// The sum of differences between every pair of lists, where `matrix[a][b]` compares the
+    /// list at column `a` to the list at column `b`
+    pub fn distance_matrix(&self) -> Vec<Vec<T>> {
+        self.matrix(|a, b| {
+            sorted_pairings(&self.sorted[a], &self.sorted[b])
+                .fold(T::default(), |sum, (&left, &right)| {
+                    sum + difference(left, right)
+                })
+        })
+    }
+
+    fn matrix<F>(&self, score: F) -> Vec<Vec<T>>
+    where
+        F: Fn(usize, usize) -> T,
+    {
+        (0..self.width())
+            .map(|a| (0..self.width()).map(|b| score(a, b)).collect())
+            .collect()
+    }
+}
+
+impl<T> MultiLocationIds<T>
+where
+    T: CheckedScore,
+{
+    /// The similarity score between every pair of lists, where `matrix[a][b]` scores each id in
+    /// the list at column `a` by how often it appears in the list at column `b`. Fails on the
+    /// first score that does not fit in `T`, as [`crate::LocationIds::checked_similarity_sum`]
+    /// does.
+    pub fn similarity_matrix(&self) -> Result<Vec<Vec<T>>, ScoreError<T>> {
+        (0..self.width())
+            .map(|a| {
+                (0..self.width())
+                    .map(|b| {
+                        self.lists[a].iter().try_fold(zero(), |sum: T, &id| {
+                            sum.checked_add(checked_similarity(id, self.count(b, &id))?)
+                                .ok_or(ScoreError::SimilaritySumOverflow { id })
+                        })
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+impl<T> FromStr for MultiLocationIds<T>
+where
+    T: Clone,
+    T: Ord,
+    T: FromStr,
+    <T as FromStr>::Err: Debug,
+{
+    type Err = ParseError<<T as FromStr>::Err>;
+
+    /// Every line must have as many columns as the first
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lists: Vec<Vec<T>> = Vec::new();
+        let mut sorted: Vec<BTreeMap<T, usize>> = Vec::new();
+        for (idx, line) in s.trim_end().lines().enumerate() {
+            let line_number = idx + 1;
+            let row = columns(line)
+                .map(|(column, token)| Ok((column, parse_id(line_number, column, token)?)))
+                .collect::<Result<Vec<(usize, T)>, Self::Err>>()?;
+            if lists.is_empty() {
+                lists.resize_with(row.len().max(1), Vec::new);
+                sorted.resize_with(lists.len(), BTreeMap::new);
+            }
+            if row.len() < lists.len() {
+                return Err(ParseError::MissingColumn {
+                    line: line_number,
+                    column: line.chars().count() + 1,
+                    expected: lists.len(),
+                    found: row.len(),
+                });
+            }
+            if let Some(&(column, _)) = row.get(lists.len()) {
+                return Err(ParseError::ExtraColumn {
+                    line: line_number,
+                    column,
+                    expected: lists.len(),
+                    found: row.len(),
+                });
+            }
+            let targets = lists.iter_mut().zip(sorted.iter_mut());
+            for ((_, id), (list, sorted)) in row.into_iter().zip(targets) {
+                *sorted.entry(id.clone()).or_insert(0usize) += 1;
+                list.push(id);
+            }
+        }
+        Ok(MultiLocationIds { lists, sorted })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn three_columns() {
+        let location_ids: MultiLocationIds<u32> = "\
+3 4 3
+4 3 3
+2 5 1
+1 3 8
+3 9 3
+3 3 3"
+            .parse()
+            .unwrap();
+        assert_eq!(location_ids.width(), 3);
+        let distances = location_ids.distance_matrix();
+        assert_eq!(
+            distances,
+            vec![vec![0, 11, 5], vec![11, 0, 6], vec![5, 6, 0]]
+        );
+        let similarities = location_ids.similarity_matrix().unwrap();
+        assert_eq!(similarities[0][1], 31);
+        assert_eq!(similarities[1][0], 31);
+        assert_eq!(similarities[0][2], 37);
+        assert_eq!(similarities[0][0], 3 * 3 * 3 + 4 + 2 + 1);
+    }
+
+    #[test]
+    fn ragged_rows() {
+        let result = "1 2 3\n4 5".parse::<MultiLocationIds<u8>>();
+        assert_eq!(
+            result.err(),
+            Some(ParseError::MissingColumn {
+                line: 2,
+                column: 4,
+                expected: 3,
+                found: 2
+            })
+        );
+        let result = "1 2\n4 5  6".parse::<MultiLocationIds<u8>>();
+        assert_eq!(
+            result.err(),
+            Some(ParseError::ExtraColumn {
+                line: 2,
+                column: 6,
+                expected: 2,
+                found: 3
+            })
+        );
+    }
+
+    #[test]
+    fn similarity_overflows() {
+        let location_ids: MultiLocationIds<u8> = "100 100 1\n100 100 1\n100 7 1".parse().unwrap();
+        assert_eq!(
+            location_ids.similarity_matrix(),
+            Err(ScoreError::SimilarityOverflow { id: 100, count: 3 })
+        );
+        let location_ids: MultiLocationIds<u8> = "200 200\n100 100".parse().unwrap();
+        assert_eq!(
+            location_ids.similarity_matrix(),
+            Err(ScoreError::SimilaritySumOverflow { id: 100 })
+        );
+        let location_ids: MultiLocationIds<u8> = "1 1\n".repeat(300).parse().unwrap();
+        assert_eq!(
+            location_ids.similarity_matrix(),
+            Err(ScoreError::CountOverflow { id: 1, count: 300 })
+        );
+    }
+}
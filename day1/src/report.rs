@@ -0,0 +1,175 @@
+use std::fmt::{self, Display};
+
+use crate::LocationIds;
+
+/// How many times one id appears in each list
+#[derive(Debug, PartialEq, Eq)]
+pub struct IdCounts<'a, T> {
+    pub id: &'a T,
+    pub left: usize,
+    pub right: usize,
+}
+
+impl<T> IdCounts<'_, T> {
+    /// How many times the id appears in both lists
+    pub fn common(&self) -> usize {
+        self.left.min(self.right)
+    }
+
+    /// How many more times the id appears in the left list than in the right
+    pub fn left_excess(&self) -> usize {
+        self.left.saturating_sub(self.right)
+    }
+
+    /// How many more times the id appears in the right list than in the left
+    pub fn right_excess(&self) -> usize {
+        self.right.saturating_sub(self.left)
+    }
+}
+
+/// The left and right lists compared as multisets, with one row per distinct id in either list,
+/// sorted by id
+#[derive(Debug, PartialEq, Eq)]
+pub struct Reconciliation<'a, T> {
+    pub rows: Vec<IdCounts<'a, T>>,
+}
+
+impl<'a, T> Reconciliation<'a, T> {
+    /// Ids that appear in the left list but never in the right
+    pub fn left_only(&self) -> Vec<(&'a T, usize)> {
+        self.select(|row| if row.right == 0 { row.left } else { 0 })
+    }
+
+    /// Ids that appear in the right list but never in the left
+    pub fn right_only(&self) -> Vec<(&'a T, usize)> {
+        self.select(|row| if row.left == 0 { row.right } else { 0 })
+    }
+
+    /// The multiset intersection: each id that appears in both lists, as many times as it appears
+    /// in both
+    pub fn intersection(&self) -> Vec<(&'a T, usize)> {
+        self.select(IdCounts::common)
+    }
+
+    /// The multiset difference left - right: each id that is left over after removing one left
+    /// occurrence for every right occurrence
+    pub fn left_difference(&self) -> Vec<(&'a T, usize)> {
+        self.select(IdCounts::left_excess)
+    }
+
+    /// The multiset difference right - left
+    pub fn right_difference(&self) -> Vec<(&'a T, usize)> {
+        self.select(IdCounts::right_excess)
+    }
+
+    fn select<F>(&self, count: F) -> Vec<(&'a T, usize)>
+    where
+        F: Fn(&IdCounts<'a, T>) -> usize,
+    {
+        self.rows
+            .iter()
+            .map(|row| (row.id, count(row)))
+            .filter(|&(_, count)| count > 0)
+            .collect()
+    }
+}
+
+impl<T> Display for Reconciliation<'_, T>
+where
+    T: Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const HEADERS: [&str; 6] = ["id", "left", "right", "common", "left-right", "right-left"];
+        let ids = self
+            .rows
+            .iter()
+            .map(|row| row.id.to_string())
+            .collect::<Vec<_>>();
+        let id_width = ids
+            .iter()
+            .map(String::len)
+            .chain([HEADERS[0].len()])
+            .max()
+            .unwrap_or_default();
+
+        write!(f, "{:<id_width$}", HEADERS[0])?;
+        for header in &HEADERS[1..] {
+            write!(f, "  {header}")?;
+        }
+        writeln!(f)?;
+        for (id, row) in ids.iter().zip(&self.rows) {
+            write!(f, "{id:<id_width$}")?;
+            let counts = [
+                row.left,
+                row.right,
+                row.common(),
+                row.left_excess(),
+                row.right_excess(),
+            ];
+            for (header, count) in HEADERS[1..].iter().zip(counts) {
+                write!(f, "  {count:>width$}", width = header.len())?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T> LocationIds<T>
+where
+    T: Ord,
+{
+    /// Compares the left and right lists as multisets
+    pub fn reconcile(&self) -> Reconciliation<'_, T> {
+        let mut left = self.sorted_left.iter().peekable();
+        let mut right = self.sorted_right.iter().peekable();
+        let mut rows = Vec::new();
+        loop {
+            let id = match (left.peek(), right.peek()) {
+                (Some(&(l, _)), Some(&(r, _))) => l.min(r),
+                (Some(&(l, _)), None) => l,
+                (None, Some(&(r, _))) => r,
+                (None, None) => break,
+            };
+            rows.push(IdCounts {
+                id,
+                left: left.next_if(|&(l, _)| l == id).map_or(0, |(_, &c)| c),
+                right: right.next_if(|&(r, _)| r == id).map_or(0, |(_, &c)| c),
+            });
+        }
+        Reconciliation { rows }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = include_str!("test_input.txt");
+
+    #[test]
+    fn reconcile_test_input() {
+        let location_ids: LocationIds<u8> = INPUT.parse().unwrap();
+        let report = location_ids.reconcile();
+        assert_eq!(report.left_only(), vec![(&1, 1), (&2, 1)]);
+        assert_eq!(report.right_only(), vec![(&5, 1), (&9, 1)]);
+        assert_eq!(report.intersection(), vec![(&3, 3), (&4, 1)]);
+        assert_eq!(report.left_difference(), vec![(&1, 1), (&2, 1)]);
+        assert_eq!(report.right_difference(), vec![(&5, 1), (&9, 1)]);
+
+        let location_ids: LocationIds<u8> = "1 1\n1 2\n1 2\n3 2".parse().unwrap();
+        let report = location_ids.reconcile();
+        assert_eq!(report.intersection(), vec![(&1, 1)]);
+        assert_eq!(report.left_difference(), vec![(&1, 2), (&3, 1)]);
+        assert_eq!(report.right_difference(), vec![(&2, 3)]);
+        assert_eq!(
+            report.to_string(),
+            "\
+id  left  right  common  left-right  right-left
+1      3      1       1           2           0
+2      0      3       0           0           3
+3      1      0       0           1           0
+"
+        );
+    }
+}
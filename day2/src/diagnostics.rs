@@ -0,0 +1,216 @@
+use std::fmt::{self, Display};
+
+use crate::{Direction, Level, SafetyPolicy};
+
+/// What is wrong with a step between two adjacent levels
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Reason {
+    /// The levels differ by more than the policy's `max_step`
+    StepTooLarge,
+    /// The levels differ, but by less than the policy's `min_step`
+    StepTooSmall,
+    /// The levels are equal, and there have been more than `max_plateau` flat steps in a row
+    ZeroStep,
+    /// The step goes the opposite way to the report's earlier steps
+    DirectionReversal,
+    /// The report trends in a direction the policy doesn't allow
+    DisallowedDirection,
+}
+
+impl Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Reason::StepTooLarge => "step too large",
+            Reason::StepTooSmall => "step too small",
+            Reason::ZeroStep => "zero step",
+            Reason::DirectionReversal => "direction reversal",
+            Reason::DisallowedDirection => "disallowed direction",
+        })
+    }
+}
+
+/// The first unsafe step in a report, from the level at index `prev` to the one at `next`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub prev: usize,
+    pub next: usize,
+    pub reason: Reason,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} between levels {} and {}",
+            self.reason, self.prev, self.next
+        )
+    }
+}
+
+/// The outcome of checking a report
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Safe,
+    /// Safe once the problem dampener removes the level at index `removed`
+    Dampened {
+        removed: usize,
+    },
+    Unsafe(Violation),
+}
+
+impl Verdict {
+    pub fn is_safe(&self) -> bool {
+        !matches!(self, Verdict::Unsafe(_))
+    }
+}
+
+impl Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Verdict::Safe => f.write_str("safe"),
+            Verdict::Dampened { removed } => write!(f, "safe without level {removed}"),
+            Verdict::Unsafe(violation) => write!(f, "unsafe: {violation}"),
+        }
+    }
+}
+
+impl SafetyPolicy {
+    /// Checks the report as it is, finding the first step that breaks the policy if it isn't safe.
+    ///
+    /// The report's direction is set by its first step between levels that differ, so a later
+    /// step the other way is a [`Reason::DirectionReversal`].
+    pub fn check_report<T: Level>(&self, level: &[T]) -> Verdict {
+        let mut trend = None;
+        let mut plateau = 0;
+        for (prev, pair) in level.windows(2).enumerate() {
+            let (from, to) = (pair[0], pair[1]);
+            let step = from.step(to);
+            let reason = if step == 0 {
+                plateau += 1;
+                (plateau > self.max_plateau).then_some(Reason::ZeroStep)
+            } else {
+                plateau = 0;
+                let dir = if to > from {
+                    Direction::Ascending
+                } else {
+                    Direction::Descending
+                };
+                if *trend.get_or_insert(dir) != dir {
+                    Some(Reason::DirectionReversal)
+                } else if !self.directions.allows(dir) {
+                    Some(Reason::DisallowedDirection)
+                } else if step > self.max_step {
+                    Some(Reason::StepTooLarge)
+                } else if step < self.min_step {
+                    Some(Reason::StepTooSmall)
+                } else {
+                    None
+                }
+            };
+            if let Some(reason) = reason {
+                return Verdict::Unsafe(Violation {
+                    prev,
+                    next: prev + 1,
+                    reason,
+                });
+            }
+        }
+        Verdict::Safe
+    }
+
+    /// Checks the report with the problem dampener. If it isn't safe even then, the violation is
+    /// the first one in the report as it is, as found by [`SafetyPolicy::check_report`].
+    pub fn check_report_with_problem_dampener<T: Level>(&self, level: &[T]) -> Verdict {
+        match self.dampened_removal(level) {
+            Some(None) => Verdict::Safe,
+            Some(Some(removed)) => Verdict::Dampened { removed },
+            None => self.check_report(level),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{check_report, check_report_with_problem_dampener, AllowedDirections};
+
+    fn unsafe_at(prev: usize, reason: Reason) -> Verdict {
+        Verdict::Unsafe(Violation {
+            prev,
+            next: prev + 1,
+            reason,
+        })
+    }
+
+    #[test]
+    fn test_input() {
+        for (level, verdict, dampened) in [
+            ([7, 6, 4, 2, 1], Verdict::Safe, Verdict::Safe),
+            (
+                [1, 2, 7, 8, 9],
+                unsafe_at(1, Reason::StepTooLarge),
+                unsafe_at(1, Reason::StepTooLarge),
+            ),
+            (
+                [9, 7, 6, 2, 1],
+                unsafe_at(2, Reason::StepTooLarge),
+                unsafe_at(2, Reason::StepTooLarge),
+            ),
+            (
+                [1, 3, 2, 4, 5],
+                unsafe_at(1, Reason::DirectionReversal),
+                Verdict::Dampened { removed: 1 },
+            ),
+            (
+                [8, 6, 4, 4, 1],
+                unsafe_at(2, Reason::ZeroStep),
+                Verdict::Dampened { removed: 2 },
+            ),
+            ([1, 3, 6, 7, 9], Verdict::Safe, Verdict::Safe),
+        ] {
+            assert_eq!(check_report(&level), verdict, "{level:?}");
+            assert_eq!(
+                check_report_with_problem_dampener(&level),
+                dampened,
+                "{level:?}"
+            );
+        }
+        assert_eq!(
+            check_report_with_problem_dampener(&[5, 1, 2, 3]),
+            Verdict::Dampened { removed: 0 }
+        );
+        assert_eq!(
+            check_report_with_problem_dampener(&[1, 2, 3, 9]),
+            Verdict::Dampened { removed: 3 }
+        );
+    }
+
+    #[test]
+    fn policy_reasons() {
+        let falling = SafetyPolicy {
+            directions: AllowedDirections::Descending,
+            min_step: 2,
+            ..Default::default()
+        };
+        assert_eq!(
+            falling.check_report(&[3, 3, 4]),
+            unsafe_at(0, Reason::ZeroStep)
+        );
+        assert_eq!(
+            falling.check_report(&[9, 7, 8]),
+            unsafe_at(1, Reason::DirectionReversal)
+        );
+        assert_eq!(
+            falling.check_report(&[1, 2]),
+            unsafe_at(0, Reason::DisallowedDirection)
+        );
+        assert_eq!(
+            falling.check_report(&[9, 7, 6]),
+            unsafe_at(1, Reason::StepTooSmall)
+        );
+        assert_eq!(
+            unsafe_at(1, Reason::StepTooSmall).to_string(),
+            "unsafe: step too small between levels 1 and 2"
+        );
+    }
+}
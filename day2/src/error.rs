@@ -0,0 +1,45 @@
+use std::fmt::Debug;
+
+use thiserror::Error;
+
+/// Why a report could not be read. Positions are given as an editor would show them: the first
+/// line and the first character of a line are both numbered 1.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ParseError<E>
+where
+    E: Debug,
+{
+    #[error("line {line}: report has no levels")]
+    EmptyReport { line: usize },
+    #[error("line {line}, column {column}: {token:?} is not a valid level: {error:?}")]
+    InvalidLevel {
+        line: usize,
+        column: usize,
+        token: String,
+        error: E,
+    },
+}
+
+impl<E> ParseError<E>
+where
+    E: Debug,
+{
+    /// The line the error was found on
+    pub fn line(&self) -> usize {
+        match self {
+            ParseError::EmptyReport { line } | ParseError::InvalidLevel { line, .. } => *line,
+        }
+    }
+}
+
+/// Why [`crate::stream::Classifier`] could not read a report
+#[derive(Debug, Error)]
+pub enum StreamError<E>
+where
+    E: Debug,
+{
+    #[error("failed to read reports: {0}")]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Parse(#[from] ParseError<E>),
+}
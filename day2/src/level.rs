@@ -0,0 +1,28 @@
+use std::fmt::Debug;
+
+/// An integer type that reports can be made of
+pub trait Level: Copy + Ord + Debug {
+    /// How far apart two levels are, which can't overflow even for signed levels
+    fn step(self, other: Self) -> u64;
+
+    /// The level `by` above this one, if it is in range
+    fn checked_offset(self, by: i128) -> Option<Self>;
+}
+
+macro_rules! impl_level {
+    ($($t:ty),*) => {
+        $(
+            impl Level for $t {
+                fn step(self, other: Self) -> u64 {
+                    <$t>::abs_diff(self, other).into()
+                }
+
+                fn checked_offset(self, by: i128) -> Option<Self> {
+                    i128::from(self).checked_add(by)?.try_into().ok()
+                }
+            }
+        )*
+    };
+}
+
+impl_level!(u8, u16, u32, u64, i8, i16, i32, i64);
@@ -1,218 +1,456 @@
-#[derive(Debug, Copy, Clone)]
-enum Direction {
+pub mod diagnostics;
+pub mod error;
+pub mod level;
+pub mod parser;
+pub mod policy;
+pub mod repair;
+pub mod segment;
+pub mod stream;
+pub mod subsequence;
+
+pub use diagnostics::{Reason, Verdict, Violation};
+pub use level::Level;
+pub use policy::{AllowedDirections, SafetyPolicy};
+pub use repair::Repair;
+pub use segment::Segment;
+pub use subsequence::SafeSubsequence;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Direction {
     Ascending,
     Descending,
 }
 
 impl Direction {
-    /// opposite was used in the "with_dampener" state machine solution
-    // fn opposite(&self) -> Self {
-    //     match self {
-    //         Direction::Ascending => Direction::Descending,
-    //         Direction::Descending => Direction::Ascending,
-    //     }
-    // }
-    fn safely(&self, prev: u8, next: u8) -> bool {
-        match self {
-            Direction::Ascending => next > prev && next - prev <= 3,
-            Direction::Descending => next < prev && prev - next <= 3,
+    /// Whether stepping from `prev` to `next` moves this way by an amount the policy allows
+    pub fn safely<T: Level>(&self, prev: T, next: T, policy: &SafetyPolicy) -> bool {
+        let step = prev.step(next);
+        let moves_this_way = match self {
+            Direction::Ascending => next > prev,
+            Direction::Descending => next < prev,
+        };
+        moves_this_way
+            && policy.directions.allows(*self)
+            && step >= policy.min_step.max(1)
+            && step <= policy.max_step
+    }
+
+    /// The direction stepping from `prev` to `next` safely moves in, if any
+    pub fn from<T: Level>(prev: T, next: T, policy: &SafetyPolicy) -> Option<Self> {
+        [Direction::Ascending, Direction::Descending]
+            .into_iter()
+            .find(|dir| dir.safely(prev, next, policy))
+    }
+}
+
+pub fn is_safe<T: Level>(level: &[T]) -> bool {
+    SafetyPolicy::default().is_safe(level)
+}
+
+pub fn is_safe_with_problem_dampener<T: Level>(level: &[T]) -> bool {
+    SafetyPolicy::default().is_safe_with_problem_dampener(level)
+}
+
+/// Why the report isn't safe, if it isn't
+pub fn check_report<T: Level>(level: &[T]) -> Verdict {
+    SafetyPolicy::default().check_report(level)
+}
+
+/// Why the report isn't safe even with the problem dampener, if it isn't, or else which level the
+/// dampener removed
+pub fn check_report_with_problem_dampener<T: Level>(level: &[T]) -> Verdict {
+    SafetyPolicy::default().check_report_with_problem_dampener(level)
+}
+
+/// Makes the report safe by overwriting at most `max_replacements` levels, changing them as little
+/// as possible in total
+pub fn repair_within<T: Level>(level: &[T], max_replacements: usize) -> Option<Repair<T>> {
+    SafetyPolicy::default().repair_within(level, max_replacements)
+}
+
+/// Splits the report into its maximal safe runs of adjacent levels
+pub fn segments<T: Level>(level: &[T]) -> Vec<Segment> {
+    SafetyPolicy::default().segments(level)
+}
+
+/// The longest subsequence of the report that is safe on its own
+pub fn longest_safe_subsequence<T: Level>(level: &[T]) -> SafeSubsequence<T> {
+    SafetyPolicy::default().longest_safe_subsequence(level)
+}
+
+/// The fewest levels that must be removed for the report to be safe. A safe report needs none, and
+/// one that is safe with the problem dampener needs at most one.
+pub fn min_removals<T: Level>(level: &[T]) -> usize {
+    SafetyPolicy::default().min_removals(level)
+}
+
+/// The indices of the fewest levels that must be removed for the report to be safe, or None if
+/// that would take more than `max` removals
+pub fn removals_within<T: Level>(level: &[T], max: usize) -> Option<Vec<usize>> {
+    SafetyPolicy::default().removals_within(level, max)
+}
+
+/// Where a report stands in one direction after reading some prefix of it, for checking it with the
+/// problem dampener in a single pass. Each field is None if the prefix can't be made safe that way,
+/// or else how many flat steps the prefix ends with (fewer is always better).
+#[derive(Debug, Copy, Clone)]
+struct DampenerState {
+    /// The prefix is safe as it is
+    clean: Option<usize>,
+    /// The prefix is safe once some level before the latest one is removed. Also holds the index
+    /// of the level that was removed.
+    removed_earlier: Option<(usize, usize)>,
+    /// The prefix is safe once the latest level is removed
+    removed_latest: Option<usize>,
+}
+
+impl DampenerState {
+    /// Before the first level is read, only the first level can be the one removed
+    const START: Self = DampenerState {
+        clean: Some(0),
+        removed_earlier: None,
+        removed_latest: Some(0),
+    };
+
+    /// Reads `next`, where `prev` is the latest level so far, found at `prev_index`, and
+    /// `prevprev` the one before it.
+    ///
+    /// Only the latest two levels ever matter: a level can follow on from `prev`, or from
+    /// `prevprev` if `prev` is the one removed.
+    fn step<T: Level>(
+        self,
+        policy: &SafetyPolicy,
+        dir: Direction,
+        prevprev: Option<T>,
+        (prev_index, prev): (usize, T),
+        next: T,
+    ) -> Self {
+        let follows_prev = |plateau| policy.extend(dir, plateau, prev, next);
+        let follows_prevprev = |plateau| match prevprev {
+            Some(prevprev) => policy.extend(dir, plateau, prevprev, next),
+            None => Some(0),
+        };
+        let removed_earlier = [
+            self.removed_earlier
+                .and_then(|(plateau, removed)| Some((follows_prev(plateau)?, removed))),
+            self.removed_latest
+                .and_then(follows_prevprev)
+                .map(|plateau| (plateau, prev_index)),
+        ];
+        DampenerState {
+            clean: self.clean.and_then(follows_prev),
+            removed_earlier: removed_earlier.into_iter().flatten().min(),
+            removed_latest: self.clean,
         }
     }
 
-    fn from(prev: u8, next: u8) -> Option<Self> {
-        match next.cmp(&prev) {
-            std::cmp::Ordering::Less if prev - next <= 3 => Some(Direction::Descending),
-            std::cmp::Ordering::Greater if next - prev <= 3 => Some(Direction::Ascending),
-            _ => None,
+    /// None if the report read so far can't be made safe by removing at most one level, or else
+    /// which level has to be removed, if any. `latest` is the index of the latest level.
+    fn removal(&self, latest: usize) -> Option<Option<usize>> {
+        if self.clean.is_some() {
+            Some(None)
+        } else if let Some((_, removed)) = self.removed_earlier {
+            Some(Some(removed))
+        } else {
+            self.removed_latest.map(|_| Some(latest))
         }
     }
 }
 
-pub fn is_safe(level: &[u8]) -> bool {
-    level
-        .iter()
-        .fold(
-            (true, None::<Direction>, None),
-            |(is_safe, direction, prev), &next| match (is_safe, direction, prev) {
-                (false, _, _) => (false, None, None),
-                (true, Some(dir), Some(prev)) => (dir.safely(prev, next), direction, Some(next)),
-                (true, None, None) => (true, None, Some(next)),
-                (true, None, Some(prev)) => {
-                    if let Some(dir) = Direction::from(prev, next) {
-                        (true, Some(dir), Some(next))
-                    } else {
-                        (false, None, None)
+/// A safe subsequence of a report, as found by [`SafetyPolicy::longest_safe_indices`]
+#[derive(Debug, Copy, Clone)]
+struct Chain {
+    len: usize,
+    /// The level before the last one in this chain, and how many flat steps the chain had there
+    from: Option<(usize, usize)>,
+}
+
+impl SafetyPolicy {
+    /// Whether the level is safe once at most one of its values is removed.
+    ///
+    /// The report is checked in every allowed direction at once, and for each direction only a few
+    /// ways of having used the dampener need to be tracked (see [`DampenerState`]), so this is
+    /// O(n).
+    pub fn is_safe_with_problem_dampener<T: Level>(&self, level: &[T]) -> bool {
+        self.dampened_removal(level).is_some()
+    }
+
+    /// None if the level isn't safe even with the problem dampener, or else the index of the
+    /// level the dampener has to remove, if any
+    pub(crate) fn dampened_removal<T: Level>(&self, level: &[T]) -> Option<Option<usize>> {
+        let directions = self.directions.iter().collect::<Vec<_>>();
+        let (states, _, _) = level.iter().enumerate().fold(
+            (vec![DampenerState::START; directions.len()], None, None),
+            |(mut states, prevprev, prev), (idx, &next)| match prev {
+                None => (states, None, Some((idx, next))),
+                Some(prev) => {
+                    for (state, &dir) in states.iter_mut().zip(&directions) {
+                        *state = state.step(self, dir, prevprev, prev, next);
                     }
+                    (states, Some(prev.1), Some((idx, next)))
                 }
-                (true, Some(_), None) => unreachable!(),
             },
+        );
+        let latest = level.len().saturating_sub(1);
+        states
+            .iter()
+            .filter_map(|state| state.removal(latest))
+            .min()
+    }
+
+    /// The indices of the longest (not necessarily contiguous) run of levels that is safe on its
+    /// own, in order.
+    ///
+    /// For each level, and each number of flat steps a safe subsequence could end on, this finds
+    /// the longest such subsequence ending at that level. That is O(n^2) in the length of the
+    /// report.
+    fn longest_safe_indices<T: Level>(&self, level: &[T]) -> Vec<usize> {
+        let plateaus = self.max_plateau.min(level.len()) + 1;
+        self.directions
+            .iter()
+            .map(|dir| {
+                // chains[i][p] is the longest chain ending at level i with p flat steps
+                let mut chains: Vec<Vec<Option<Chain>>> = Vec::with_capacity(level.len());
+                for &next in level {
+                    let mut here = vec![None; plateaus];
+                    here[0] = Some(Chain { len: 1, from: None });
+                    for (j, before) in chains.iter().enumerate() {
+                        for (plateau, chain) in before.iter().enumerate() {
+                            let Some(chain) = chain else { continue };
+                            let Some(after) = self.extend(dir, plateau, level[j], next) else {
+                                continue;
+                            };
+                            if here[after].is_none_or(|best| chain.len + 1 > best.len) {
+                                here[after] = Some(Chain {
+                                    len: chain.len + 1,
+                                    from: Some((j, plateau)),
+                                });
+                            }
+                        }
+                    }
+                    chains.push(here);
+                }
+
+                let mut cur = chains
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(i, here)| {
+                        here.iter().enumerate().filter_map(move |(plateau, chain)| {
+                            Some((chain.as_ref()?.len, (i, plateau)))
+                        })
+                    })
+                    .max()
+                    .map(|(_, end)| end);
+                let mut kept = Vec::new();
+                while let Some((i, plateau)) = cur {
+                    kept.push(i);
+                    cur = chains[i][plateau].and_then(|chain| chain.from);
+                }
+                kept.reverse();
+                kept
+            })
+            .max_by_key(Vec::len)
+            .unwrap_or_default()
+    }
+
+    /// The fewest levels that must be removed for the report to be safe
+    pub fn min_removals<T: Level>(&self, level: &[T]) -> usize {
+        level.len() - self.longest_safe_indices(level).len()
+    }
+
+    /// The indices of the fewest levels that must be removed for the report to be safe, or None
+    /// if that would take more than `max` removals
+    pub fn removals_within<T: Level>(&self, level: &[T], max: usize) -> Option<Vec<usize>> {
+        let kept = self.longest_safe_indices(level);
+        if level.len() - kept.len() > max {
+            return None;
+        }
+        let mut kept = kept.into_iter().peekable();
+        Some(
+            (0..level.len())
+                .filter(|&i| kept.next_if_eq(&i).is_none())
+                .collect(),
         )
-        .0
-}
-
-pub fn is_safe_with_problem_dampener(level: &[u8]) -> bool {
-    if is_safe(level) { true } else {
-
-        (0..level.len()).map(|i| {
-            let before = &level[..i];
-            let after = &level[i + 1usize..];
-            [before, after].concat()
-        }).any(|modified_level| is_safe(&modified_level))
-    }
-}
-
-// There's a solution here using a similar state machine stepper logic, but tracking more
-// individual states. This should allow a solution in O(n) complexity. However, I couldn't get the
-// edge cases working right in a reasonable amount of time. The conflicting cases were:
-//
-// 57 55 58 61 63 64 65 67
-//
-// Where dropping the first element would allow the series to be ascending safely. I could not find
-// a safe way to checking for that in O(n) time without breaking one of the test cases:
-//
-// 1 3 2 4 5
-//
-// Where dropping the _second_ element would allow the series to continue to ascend safely. Since
-// dropping the first element will also allow the series to continue _descending_ safely briefly, I
-// end up having to fork the logic and lose O(n) immediately.
-//
-// I'm confident that there's a solution down this path, but I'm not experienced enough to solve
-// it. Instead I threw more compute at it and just calculated each level were it missing any one of its
-// elements, lazily checking if any of those variations were safe instead.
-//
-// I leave my aborted state machine (in all its unreadable complexity -- I hadn't gotten around to
-// cleaning any of this up before abandoning course) in case anyone would like to follow along with
-// my logic
-//
-//
-// pub fn is_safe_with_problem_dampener(level: &[u8]) -> bool {
-//     let result = level.iter().fold(
-//         (true, false, true, None::<Direction>, None, None),
-//         |(is_safe, dampener_used, can_change_course, direction, prevprev, prev), &next| match (
-//             is_safe,
-//             dampener_used,
-//             can_change_course,
-//             direction,
-//             prevprev,
-//             prev,
-//         ) {
-//             (_, _, _, _, Some(_), None) => unreachable!("Can't have a prevprev without a prev"),
-//             (false, false, _, None, None, _) => {
-//                 unreachable!("Can't fail (the first time) before getting to two values")
-//             }
-//             (true, _, _, None, Some(_), Some(_)) => {
-//                 unreachable!("Can't have a safe level with two values without a direction")
-//             }
-//             (_, _, _, Some(_), None, Some(_)) | (_, _, _, Some(_), None, None) => {
-//                 unreachable!("Can't have a direction without two values")
-//             }
-//             (_, _, _, None, Some(_), Some(_)) => unreachable!("Can't have two values without a direction"),
-//             (false, true, _, _, _, _) => (false, true, false, None, None, None),
-//             // dampener can be effectively used
-//             (false, false, false, Some(dir), Some(prevprev), _) => (
-//                 dir.safely(prevprev, next),
-//                 true,
-//                 can_change_course,
-//                 Some(dir),
-//                 Some(prevprev),
-//                 Some(next),
-//             ),
-//             // If we can still change course, give it a try
-//             (false, false, true, _, Some(prevprev), Some(_)) => {
-//                 if let Some(dir) = Direction::from(prevprev, next) {
-//                     (
-//                         dir.safely(prevprev, next),
-//                         true,
-//                         true,
-//                         Some(dir),
-//                         Some(prevprev),
-//                         Some(next),
-//                     )
-//                 } else {
-//                     (false, true, can_change_course, None, None, None)
-//                 }
-//             }
-//             (true, _, _, None, None, None) => (
-//                 true,
-//                 dampener_used,
-//                 can_change_course,
-//                 None,
-//                 None,
-//                 Some(next),
-//             ),
-//             (true, _, _, None, None, Some(prev)) => {
-//                 if let Some(dir) = Direction::from(prev, next) {
-//                     (
-//                         true,
-//                         dampener_used,
-//                         can_change_course,
-//                         Some(dir),
-//                         Some(prev),
-//                         Some(next),
-//                     )
-//                 } else {
-//                     (
-//                         false,
-//                         dampener_used,
-//                         can_change_course,
-//                         None,
-//                         Some(prev),
-//                         Some(next),
-//                     )
-//                 }
-//             }
-//             (true, _, true, Some(dir), Some(_), Some(prev)) => {
-//                 if dir.safely(prev, next) {
-//                     (
-//                         true,
-//                         dampener_used,
-//                         false,
-//                         Some(dir),
-//                         Some(prev),
-//                         Some(next),
-//                     )
-//                 } else if dir.opposite().safely(prev, next) {
-//                     (
-//                         true,
-//                         true,
-//                         false,
-//                         Some(dir.opposite()),
-//                         Some(prev),
-//                         Some(next),
-//                     )
-//                 } else {
-//                     (
-//                         false,
-//                         dampener_used,
-//                         true,
-//                         Some(dir),
-//                         Some(prev),
-//                         Some(next),
-//                     )
-//                 }
-//             }
-//             (true, _, false, Some(dir), Some(_), Some(prev)) => {
-//                 if dir.safely(prev, next) {
-//                     (
-//                         true,
-//                         dampener_used,
-//                         false,
-//                         Some(dir),
-//                         Some(prev),
-//                         Some(next),
-//                     )
-//                 } else {
-//                     (
-//                         false,
-//                         dampener_used,
-//                         false,
-//                         Some(dir),
-//                         Some(prev),
-//                         Some(next),
-//                     )
-//                 }
-//             }
-//         },
-//     );
-//     result.0 || !result.1 // catches the case where the final value should be ignored
-// }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    use super::*;
+
+    /// Removes each level in turn and checks what's left. This was the original solution.
+    fn brute_force_dampener(policy: &SafetyPolicy, level: &[u8]) -> bool {
+        policy.is_safe(level)
+            || (0..level.len()).any(|i| policy.is_safe(&[&level[..i], &level[i + 1..]].concat()))
+    }
+
+    /// Tries removing every subset of the levels, smallest first
+    fn brute_force_removals(policy: &SafetyPolicy, level: &[u8]) -> usize {
+        (0u32..1 << level.len())
+            .filter(|removed| {
+                let kept = level
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| removed & (1 << i) == 0)
+                    .map(|(_, &l)| l)
+                    .collect::<Vec<_>>();
+                policy.is_safe(&kept)
+            })
+            .map(u32::count_ones)
+            .min()
+            .unwrap() as usize
+    }
+
+    fn random_report(rng: &mut impl Rng, max_len: usize) -> Vec<u8> {
+        let len = rng.gen_range(0..max_len);
+        let start = rng.gen_range(10..50);
+        // mostly small steps, so that a good share of the reports are (nearly) safe
+        (0..len)
+            .scan(start, |level: &mut u8, _| {
+                *level = level.saturating_add_signed(rng.gen_range(-4..=4));
+                Some(*level)
+            })
+            .collect()
+    }
+
+    pub(crate) fn random_policy(rng: &mut impl Rng) -> SafetyPolicy {
+        let min_step = rng.gen_range(1..=2);
+        SafetyPolicy {
+            min_step,
+            max_step: rng.gen_range(min_step..=4),
+            directions: [
+                AllowedDirections::Either,
+                AllowedDirections::Ascending,
+                AllowedDirections::Descending,
+            ][rng.gen_range(0..3)],
+            max_plateau: rng.gen_range(0..=2),
+        }
+    }
+
+    #[test]
+    fn edge_cases() {
+        for (level, expected) in [
+            (vec![57, 55, 58, 61, 63, 64, 65, 67], true),
+            (vec![1, 3, 2, 4, 5], true),
+            (vec![1, 2, 7, 8, 9], false),
+            (vec![8, 6, 4, 4, 1], true),
+            (vec![1, 2, 3, 4, 9], true),
+            (vec![5, 1, 2, 3], true),
+            (vec![1, 5, 1, 2], false),
+            (vec![3, 3], true),
+            (vec![3], true),
+            (vec![], true),
+        ] {
+            assert_eq!(is_safe_with_problem_dampener(&level), expected, "{level:?}");
+        }
+    }
+
+    #[test]
+    fn agrees_with_brute_force() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let default = SafetyPolicy::default();
+        for _ in 0..100_000 {
+            let level = random_report(&mut rng, 10);
+            assert_eq!(
+                is_safe_with_problem_dampener(&level),
+                brute_force_dampener(&default, &level),
+                "{level:?}"
+            );
+
+            let policy = random_policy(&mut rng);
+            assert_eq!(
+                policy.is_safe_with_problem_dampener(&level),
+                brute_force_dampener(&policy, &level),
+                "{level:?} {policy:?}"
+            );
+
+            assert_eq!(
+                policy.check_report(&level).is_safe(),
+                policy.is_safe(&level),
+                "{level:?} {policy:?}"
+            );
+            match policy.check_report_with_problem_dampener(&level) {
+                Verdict::Safe => assert!(policy.is_safe(&level)),
+                Verdict::Dampened { removed } => assert!(
+                    !policy.is_safe(&level)
+                        && policy.is_safe(&[&level[..removed], &level[removed + 1..]].concat()),
+                    "{level:?} {policy:?}"
+                ),
+                Verdict::Unsafe(violation) => {
+                    assert!(!brute_force_dampener(&policy, &level));
+                    assert_eq!(policy.check_report(&level), Verdict::Unsafe(violation));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn removals_agree_with_brute_force() {
+        let mut rng = StdRng::seed_from_u64(11);
+        for _ in 0..5_000 {
+            let level = random_report(&mut rng, 9);
+            let policy = random_policy(&mut rng);
+            let removals = policy.min_removals(&level);
+            assert_eq!(
+                removals,
+                brute_force_removals(&policy, &level),
+                "{level:?} {policy:?}"
+            );
+            let removed = policy.removals_within(&level, removals).unwrap();
+            let kept = (0..level.len())
+                .filter(|i| !removed.contains(i))
+                .map(|i| level[i])
+                .collect::<Vec<_>>();
+            assert!(policy.is_safe(&kept), "{level:?} {policy:?}");
+        }
+    }
+
+    #[test]
+    fn policies() {
+        let flat = SafetyPolicy {
+            max_plateau: 1,
+            ..Default::default()
+        };
+        assert!(!is_safe(&[1, 2, 2, 3]));
+        assert!(flat.is_safe(&[1, 2, 2, 3, 3, 4]));
+        assert!(!flat.is_safe(&[1, 2, 2, 2, 3]));
+        assert!(flat.is_safe_with_problem_dampener(&[1, 2, 2, 2, 3]));
+        assert!(flat.is_safe(&[5, 5]));
+
+        let falling = SafetyPolicy {
+            directions: AllowedDirections::Descending,
+            ..Default::default()
+        };
+        assert!(falling.is_safe(&[7, 6, 4, 2, 1]));
+        assert!(!falling.is_safe(&[1, 3, 6, 7, 9]));
+        assert_eq!(falling.min_removals(&[1, 3, 6, 7, 9]), 4);
+
+        let wide = SafetyPolicy {
+            min_step: 2,
+            max_step: 5,
+            ..Default::default()
+        };
+        assert!(wide.is_safe(&[1, 3, 8, 10]));
+        assert!(!wide.is_safe(&[1, 2, 4]));
+        assert!(wide.is_safe_with_problem_dampener(&[1, 2, 4]));
+    }
+
+    #[test]
+    fn removals() {
+        assert_eq!(min_removals(&[7, 6, 4, 2, 1]), 0);
+        assert_eq!(min_removals(&[1, 3, 2, 4, 5]), 1);
+        assert_eq!(min_removals(&[1, 2, 7, 8, 9]), 2);
+        assert_eq!(min_removals(&[9, 1, 2, 8, 3, 9, 4]), 3);
+        assert_eq!(min_removals::<u8>(&[]), 0);
+
+        assert_eq!(removals_within(&[1, 2, 7, 8, 9], 1), None);
+        assert_eq!(removals_within(&[1, 2, 7, 8, 9], 2), Some(vec![0, 1]));
+        assert_eq!(
+            removals_within(&[9, 1, 2, 8, 3, 9, 4], 5),
+            Some(vec![0, 3, 5])
+        );
+        assert_eq!(removals_within(&[7, 6, 4, 2, 1], 0), Some(vec![]));
+    }
+}
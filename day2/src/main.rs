@@ -1,22 +1,12 @@
-#![feature(array_windows)]
-
-use day2::{is_safe, is_safe_with_problem_dampener};
+use day2::{stream::classify_reports, Verdict};
 
 const INPUT: &str = include_str!("input.txt");
 
-fn parse_input(input: &str) -> Vec<Vec<u8>> {
-    input
-        .trim()
-        .lines()
-        .map(|line| line.split_whitespace().flat_map(str::parse).collect())
-        .collect()
-}
-
 #[allow(dead_code)]
 /// This was useful in figuring out which level was failing to show as safe
-fn debug_levels<F>(levels: &[Vec<u8>], safety_fn: F)
+fn debug_levels<F>(levels: &[Vec<u8>], check_fn: F)
 where
-    F: Fn(&[u8]) -> bool,
+    F: Fn(&[u8]) -> Verdict,
 {
     for level in levels.iter() {
         let values = level
@@ -29,34 +19,31 @@ where
             .map(|[prev, next]| (*next as i8 - *prev as i8).to_string())
             .collect::<Vec<String>>()
             .join(" ");
-        let is_safe = safety_fn(level);
+        let verdict = check_fn(level);
         println!(
-            "{} {} || {}",
-            if is_safe { "    SAFE" } else { "NOT SAFE" },
+            "{} {} || {} || {}",
+            if verdict.is_safe() {
+                "    SAFE"
+            } else {
+                "NOT SAFE"
+            },
             values,
-            diffs
+            diffs,
+            verdict
         );
     }
 }
 
 fn solve_part_1(input: &str) -> usize {
-    let parsed_input = parse_input(input);
-    let safe_levels = parsed_input
-        .into_iter()
-        .filter(|line| is_safe(line))
-        .collect::<Vec<_>>();
-
-    safe_levels.len()
+    classify_reports::<_, u8>(input.as_bytes(), Default::default())
+        .expect("puzzle input should be valid reports")
+        .safe
 }
 
 fn solve_part_2(input: &str) -> usize {
-    let parsed_input = parse_input(input);
-    let safe_levels = parsed_input
-        .into_iter()
-        .filter(|line| is_safe_with_problem_dampener(line))
-        .collect::<Vec<_>>();
-
-    safe_levels.len()
+    classify_reports::<_, u8>(input.as_bytes(), Default::default())
+        .expect("puzzle input should be valid reports")
+        .safe_with_dampener()
 }
 
 fn main() {
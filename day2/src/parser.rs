@@ -0,0 +1,115 @@
+use std::{fmt::Debug, str::FromStr};
+
+use crate::{error::ParseError, Level};
+
+/// Reads one report per line, each a whitespace separated list of levels.
+///
+/// Unlike splitting and parsing each token on its own, this fails on the first token that isn't a
+/// valid level, including ones that are out of range for `T`. Trailing blank lines are ignored,
+/// but any other blank line is an error.
+pub fn parse_reports<T>(input: &str) -> Result<Vec<Vec<T>>, ParseError<T::Err>>
+where
+    T: Level + FromStr,
+    <T as FromStr>::Err: Debug,
+{
+    input
+        .trim_end()
+        .lines()
+        .zip(1..)
+        .map(|(line, line_number)| parse_report(line_number, line))
+        .collect()
+}
+
+/// Reads the levels of a single report from `line`, which is line `line_number` of the input
+pub fn parse_report<T>(line_number: usize, line: &str) -> Result<Vec<T>, ParseError<T::Err>>
+where
+    T: Level + FromStr,
+    <T as FromStr>::Err: Debug,
+{
+    let levels = tokens(line)
+        .map(|(column, token)| {
+            token.parse().map_err(|error| ParseError::InvalidLevel {
+                line: line_number,
+                column,
+                token: token.to_string(),
+                error,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if levels.is_empty() {
+        return Err(ParseError::EmptyReport { line: line_number });
+    }
+    Ok(levels)
+}
+
+/// The text of each level in a report, along with the character (counting from 1) it starts at
+fn tokens(line: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut rest = line;
+    let mut column = 1;
+    std::iter::from_fn(move || {
+        let start = rest.find(|c: char| !c.is_whitespace())?;
+        column += rest[..start].chars().count();
+        rest = &rest[start..];
+        let (token, after) = rest.split_at(rest.find(char::is_whitespace).unwrap_or(rest.len()));
+        rest = after;
+        let at = column;
+        column += token.chars().count();
+        Some((at, token))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wider_levels() {
+        assert_eq!(
+            parse_reports::<u16>("1 300 2\n  70000\n"),
+            Err(ParseError::InvalidLevel {
+                line: 2,
+                column: 3,
+                token: "70000".to_string(),
+                error: "70000".parse::<u16>().unwrap_err(),
+            })
+        );
+        assert_eq!(
+            parse_reports::<i64>("-3 -1 2\n5000000000 4999999998\n"),
+            Ok(vec![vec![-3, -1, 2], vec![5_000_000_000, 4_999_999_998]])
+        );
+        assert!(matches!(
+            parse_reports::<u8>("1 2 3\n1 2 256"),
+            Err(ParseError::InvalidLevel {
+                line: 2,
+                column: 5,
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse_reports::<i32>("1 2 x3"),
+            Err(ParseError::InvalidLevel {
+                line: 1,
+                column: 5,
+                ..
+            })
+        ));
+        assert_eq!(parse_reports::<i32>("1 2\n\n3 4").unwrap_err().line(), 2);
+        // columns count characters, not bytes
+        assert!(matches!(
+            parse_reports::<u8>("1\u{a0}\tx"),
+            Err(ParseError::InvalidLevel { column: 4, .. })
+        ));
+    }
+
+    #[test]
+    fn generic_safety() {
+        let reports = parse_reports::<i64>("-3 -1 2\n5000000000 4999999998\n0 -4").unwrap();
+        let safe = reports
+            .iter()
+            .map(|report| crate::is_safe(report))
+            .collect::<Vec<_>>();
+        assert_eq!(safe, vec![true, true, false]);
+        assert!(crate::is_safe(&[i8::MIN, i8::MIN + 3]));
+        assert!(!crate::is_safe(&[i8::MIN, i8::MAX]));
+    }
+}
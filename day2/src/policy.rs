@@ -0,0 +1,84 @@
+use crate::{Direction, Level};
+
+/// Which way a safe report may trend
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AllowedDirections {
+    Ascending,
+    Descending,
+    Either,
+}
+
+impl AllowedDirections {
+    pub fn allows(&self, dir: Direction) -> bool {
+        matches!(
+            (self, dir),
+            (AllowedDirections::Either, _)
+                | (AllowedDirections::Ascending, Direction::Ascending)
+                | (AllowedDirections::Descending, Direction::Descending)
+        )
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Direction> + '_ {
+        [Direction::Ascending, Direction::Descending]
+            .into_iter()
+            .filter(|&dir| self.allows(dir))
+    }
+}
+
+/// What makes a report safe. The default is the puzzle's rules: every step is an increase of 1 to 3,
+/// or every step is a decrease of 1 to 3.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SafetyPolicy {
+    /// The smallest change allowed between adjacent levels that differ. Steps where the level
+    /// stays the same are governed by `max_plateau` instead.
+    pub min_step: u64,
+    /// The largest change allowed between adjacent levels
+    pub max_step: u64,
+    pub directions: AllowedDirections,
+    /// How many flat steps (adjacent levels that are equal) in a row are tolerated
+    pub max_plateau: usize,
+}
+
+impl Default for SafetyPolicy {
+    fn default() -> Self {
+        SafetyPolicy {
+            min_step: 1,
+            max_step: 3,
+            directions: AllowedDirections::Either,
+            max_plateau: 0,
+        }
+    }
+}
+
+impl SafetyPolicy {
+    /// Steps from `prev` to `next` in a report trending in `dir`, where `plateau` is how many flat
+    /// steps came just before. Returns how many flat steps there are after this one, or None if
+    /// the step isn't safe.
+    pub(crate) fn extend<T: Level>(
+        &self,
+        dir: Direction,
+        plateau: usize,
+        prev: T,
+        next: T,
+    ) -> Option<usize> {
+        if prev == next {
+            Some(plateau + 1).filter(|&plateau| plateau <= self.max_plateau)
+        } else if dir.safely(prev, next, self) {
+            Some(0)
+        } else {
+            None
+        }
+    }
+
+    /// Whether the level is safe as it is
+    pub fn is_safe<T: Level>(&self, level: &[T]) -> bool {
+        self.directions.iter().any(|dir| {
+            level
+                .windows(2)
+                .try_fold(0, |plateau, pair| {
+                    self.extend(dir, plateau, pair[0], pair[1])
+                })
+                .is_some()
+        })
+    }
+}
@@ -0,0 +1,281 @@
+use crate::{Direction, Level, SafetyPolicy};
+
+/// A report made safe by overwriting some of its levels
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repair<T> {
+    /// The report after the repair
+    pub levels: Vec<T>,
+    /// The indices of the levels that were overwritten, in order
+    pub replaced: Vec<usize>,
+    /// The sum of how far each replaced level moved
+    pub total_change: u128,
+}
+
+/// The cheapest way found to reach some state of [`SafetyPolicy::repair_within`]'s search
+#[derive(Debug, Copy, Clone)]
+struct Reached {
+    cost: u128,
+    /// The state at the level before, if there is one
+    from: Option<usize>,
+}
+
+impl SafetyPolicy {
+    /// Makes the report safe by overwriting at most `max_replacements` of its levels, changing them
+    /// as little as possible in total, or returns None if that isn't enough.
+    ///
+    /// Some cheapest repair only ever uses values that are a whole number of minimum, maximum or
+    /// flat steps away from a level that was kept, through a run of replaced levels. So only those
+    /// values are tried, and the search is a DP over each level's candidate values, how many levels
+    /// have been replaced so far, and how many flat steps the report ends on. That's polynomial in
+    /// `max_replacements` and the length of the report.
+    pub fn repair_within<T: Level>(
+        &self,
+        level: &[T],
+        max_replacements: usize,
+    ) -> Option<Repair<T>> {
+        if level.is_empty() {
+            return Some(Repair {
+                levels: Vec::new(),
+                replaced: Vec::new(),
+                total_change: 0,
+            });
+        }
+        self.directions
+            .iter()
+            .filter_map(|dir| self.repair_towards(dir, level, max_replacements))
+            .min_by_key(|repair| repair.total_change)
+    }
+
+    fn repair_towards<T: Level>(
+        &self,
+        dir: Direction,
+        level: &[T],
+        max_replacements: usize,
+    ) -> Option<Repair<T>> {
+        let replacements = max_replacements.min(level.len()) + 1;
+        let plateaus = self.max_plateau.min(level.len()) + 1;
+        let candidates = (0..level.len())
+            .map(|i| self.candidates(dir, level, i, replacements - 1))
+            .collect::<Vec<_>>();
+        // states are indexed by candidate, then replacements used, then flat steps
+        let state = |candidate: usize, replaced: usize, plateau: usize| {
+            (candidate * replacements + replaced) * plateaus + plateau
+        };
+
+        let mut reached: Vec<Vec<Option<Reached>>> = Vec::with_capacity(level.len());
+        let mut first = vec![None; candidates[0].len() * replacements * plateaus];
+        for (c, &value) in candidates[0].iter().enumerate() {
+            let replaced = usize::from(value != level[0]);
+            if replaced < replacements {
+                first[state(c, replaced, 0)] = Some(Reached {
+                    cost: value.step(level[0]).into(),
+                    from: None,
+                });
+            }
+        }
+        reached.push(first);
+
+        for i in 1..level.len() {
+            let mut here = vec![None; candidates[i].len() * replacements * plateaus];
+            for (c, &value) in candidates[i].iter().enumerate() {
+                let change = u128::from(value.step(level[i]));
+                let replacing = usize::from(value != level[i]);
+                for (prev_c, &prev) in candidates[i - 1].iter().enumerate() {
+                    for replaced in 0..replacements - replacing {
+                        for plateau in 0..plateaus {
+                            let from = state(prev_c, replaced, plateau);
+                            let Some(before) = reached[i - 1][from] else {
+                                continue;
+                            };
+                            let Some(after) = self.extend(dir, plateau, prev, value) else {
+                                continue;
+                            };
+                            let cost = before.cost + change;
+                            let to = &mut here[state(c, replaced + replacing, after)];
+                            if to.is_none_or(|best: Reached| cost < best.cost) {
+                                *to = Some(Reached {
+                                    cost,
+                                    from: Some(from),
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+            reached.push(here);
+        }
+
+        let (mut cur, total_change) = reached[level.len() - 1]
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, reached)| Some((idx, reached.as_ref()?.cost)))
+            .min_by_key(|&(_, cost)| cost)?;
+        let mut levels = Vec::with_capacity(level.len());
+        for i in (0..level.len()).rev() {
+            levels.push(candidates[i][cur / (replacements * plateaus)]);
+            cur = reached[i][cur]
+                .and_then(|reached| reached.from)
+                .unwrap_or_default();
+        }
+        levels.reverse();
+        let replaced = (0..level.len())
+            .filter(|&i| levels[i] != level[i])
+            .collect();
+        Some(Repair {
+            levels,
+            replaced,
+            total_change,
+        })
+    }
+
+    /// The values worth trying for level `i` when repairing towards `dir`: its own value, and each
+    /// value reached from a level up to `max_distance` away by steps that are all of the minimum,
+    /// maximum or zero size.
+    fn candidates<T: Level>(
+        &self,
+        dir: Direction,
+        level: &[T],
+        i: usize,
+        max_distance: usize,
+    ) -> Vec<T> {
+        let min_step = i128::from(self.min_step.max(1));
+        let max_step = i128::from(self.max_step);
+        let max_flat = if self.max_plateau > 0 {
+            max_distance
+        } else {
+            0
+        };
+        let rising = match dir {
+            Direction::Ascending => 1,
+            Direction::Descending => -1,
+        };
+        let mut candidates = vec![level[i]];
+        for distance in 1..=max_distance {
+            let neighbours = [
+                i.checked_sub(distance).map(|j| (j, rising)),
+                Some(i + distance)
+                    .filter(|&j| j < level.len())
+                    .map(|j| (j, -rising)),
+            ];
+            for (j, sign) in neighbours.into_iter().flatten() {
+                for flat in 0..=max_flat.min(distance) {
+                    for at_min in 0..=distance - flat {
+                        let at_max = distance - flat - at_min;
+                        let offset = sign * (at_min as i128 * min_step + at_max as i128 * max_step);
+                        candidates.extend(level[j].checked_offset(offset));
+                    }
+                }
+            }
+        }
+        candidates.sort_unstable();
+        candidates.dedup();
+        candidates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+    use crate::tests::random_policy;
+
+    /// Tries every way of overwriting up to `max` levels with values up to 70
+    fn brute_force(policy: &SafetyPolicy, level: &[u8], max: usize) -> Option<u128> {
+        fn go(
+            policy: &SafetyPolicy,
+            level: &mut Vec<u8>,
+            original: &[u8],
+            from: usize,
+            left: usize,
+        ) -> Option<u128> {
+            let mut best = policy.is_safe(level).then(|| {
+                level
+                    .iter()
+                    .zip(original)
+                    .map(|(&l, &o)| u128::from(l.abs_diff(o)))
+                    .sum()
+            });
+            if left == 0 {
+                return best;
+            }
+            for i in from..level.len() {
+                for value in 0..=70 {
+                    if value == original[i] {
+                        continue;
+                    }
+                    level[i] = value;
+                    let found = go(policy, level, original, i + 1, left - 1);
+                    best = best.into_iter().chain(found).min();
+                }
+                level[i] = original[i];
+            }
+            best
+        }
+        go(policy, &mut level.to_vec(), level, 0, max)
+    }
+
+    #[test]
+    fn test_input() {
+        let policy = SafetyPolicy::default();
+        assert_eq!(
+            policy
+                .repair_within(&[7, 6, 4, 2, 1], 0)
+                .unwrap()
+                .total_change,
+            0
+        );
+        assert_eq!(policy.repair_within(&[1, 2, 7, 8, 9], 0), None);
+        assert_eq!(
+            policy.repair_within(&[1, 2, 7, 8, 9], 1),
+            Some(Repair {
+                levels: vec![1, 2, 5, 8, 9],
+                replaced: vec![2],
+                total_change: 2,
+            })
+        );
+        assert_eq!(policy.repair_within(&[1, 3, 2, 4, 5], 1), None);
+        assert_eq!(
+            policy
+                .repair_within(&[1, 3, 2, 4, 5], 2)
+                .unwrap()
+                .total_change,
+            2
+        );
+        assert_eq!(
+            policy
+                .repair_within(&[8, 6, 4, 4, 1], 1)
+                .unwrap()
+                .total_change,
+            1
+        );
+        assert_eq!(
+            policy
+                .repair_within(&[i64::MIN, i64::MAX], 1)
+                .unwrap()
+                .total_change,
+            u128::from(u64::MAX) - 3
+        );
+    }
+
+    #[test]
+    fn agrees_with_brute_force() {
+        let mut rng = StdRng::seed_from_u64(17);
+        for _ in 0..200 {
+            let len = rng.gen_range(0..6);
+            let level = (0..len).map(|_| rng.gen_range(20..40)).collect::<Vec<u8>>();
+            let policy = random_policy(&mut rng);
+            let max = rng.gen_range(0..=2);
+            let repair = policy.repair_within(&level, max);
+            assert_eq!(
+                repair.as_ref().map(|repair| repair.total_change),
+                brute_force(&policy, &level, max),
+                "{level:?} {policy:?} {max}"
+            );
+            if let Some(repair) = repair {
+                assert!(policy.is_safe(&repair.levels));
+                assert!(repair.replaced.len() <= max);
+            }
+        }
+    }
+}
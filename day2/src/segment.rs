@@ -0,0 +1,161 @@
+use std::ops::Range;
+
+use crate::{Direction, Level, SafetyPolicy};
+
+/// A maximal run of adjacent levels that is safe on its own
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    /// None if the run has no steps between levels that differ, so could go either way
+    pub direction: Option<Direction>,
+    /// The indices of the levels in the run
+    pub range: Range<usize>,
+}
+
+impl SafetyPolicy {
+    /// Splits the report into every run of adjacent levels that is safe, and can't be made longer
+    /// on either side while staying safe, in order.
+    ///
+    /// A run that ends where the report changes direction shares its last level with the next run,
+    /// along with any flat steps just before it, while a run that ends in a step that's too large
+    /// doesn't overlap the next one at all. So the points where one run ends and the next begins
+    /// are where the trend reverses or jumps.
+    pub fn segments<T: Level>(&self, level: &[T]) -> Vec<Segment> {
+        let mut segments = Vec::new();
+        // for each allowed direction, the start of the longest run ending at the latest level that
+        // is safe in that direction, and how many flat steps that run ends with
+        let mut runs = self
+            .directions
+            .iter()
+            .map(|dir| (dir, 0, 0))
+            .collect::<Vec<_>>();
+        // the start of the longest safe run ending at the latest level
+        let mut start = 0;
+        for end in 2..=level.len() {
+            let (prev, next) = (level[end - 2], level[end - 1]);
+            for (dir, run_start, plateau) in &mut runs {
+                match self.extend(*dir, *plateau, prev, next) {
+                    Some(flat) => *plateau = flat,
+                    // too many flat steps, so the run keeps only as many as are allowed
+                    None if prev == next => {
+                        *run_start = end - 1 - self.max_plateau;
+                        *plateau = self.max_plateau;
+                    }
+                    None => {
+                        *run_start = end - 1;
+                        *plateau = 0;
+                    }
+                }
+            }
+            let next_start = runs
+                .iter()
+                .map(|&(_, run_start, _)| run_start)
+                .min()
+                .unwrap_or(end - 1);
+            if next_start > start {
+                segments.push(self.segment(level, start..end - 1));
+                start = next_start;
+            }
+        }
+        if !level.is_empty() {
+            segments.push(self.segment(level, start..level.len()));
+        }
+        segments
+    }
+
+    fn segment<T: Level>(&self, level: &[T], range: Range<usize>) -> Segment {
+        let direction = level[range.clone()]
+            .windows(2)
+            .find_map(|pair| Direction::from(pair[0], pair[1], self));
+        Segment { direction, range }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+    use crate::{segments, tests::random_policy};
+
+    fn segment(direction: Option<Direction>, range: Range<usize>) -> Segment {
+        Segment { direction, range }
+    }
+
+    #[test]
+    fn reversals_and_jumps() {
+        use Direction::*;
+        assert_eq!(
+            segments(&[1, 2, 3, 2, 1]),
+            vec![
+                segment(Some(Ascending), 0..3),
+                segment(Some(Descending), 2..5)
+            ]
+        );
+        assert_eq!(
+            segments(&[1, 2, 3, 9, 10]),
+            vec![
+                segment(Some(Ascending), 0..3),
+                segment(Some(Ascending), 3..5)
+            ]
+        );
+        assert_eq!(
+            segments(&[1, 10, 20]),
+            vec![
+                segment(None, 0..1),
+                segment(None, 1..2),
+                segment(None, 2..3)
+            ]
+        );
+        assert_eq!(segments::<u8>(&[]), vec![]);
+
+        let flat = SafetyPolicy {
+            max_plateau: 1,
+            ..Default::default()
+        };
+        assert_eq!(
+            flat.segments(&[1, 2, 2, 1]),
+            vec![
+                segment(Some(Ascending), 0..3),
+                segment(Some(Descending), 1..4)
+            ]
+        );
+        assert_eq!(
+            flat.segments(&[5, 5, 5]),
+            vec![segment(None, 0..2), segment(None, 1..3)]
+        );
+    }
+
+    #[test]
+    fn segments_are_all_maximal_safe_runs() {
+        let mut rng = StdRng::seed_from_u64(18);
+        for _ in 0..10_000 {
+            let len = rng.gen_range(0..10);
+            let level = (0..len).map(|_| rng.gen_range(0..8)).collect::<Vec<u8>>();
+            let policy = random_policy(&mut rng);
+            let safe = |range: Range<usize>| policy.is_safe(&level[range]);
+            let expected = (0..len)
+                .flat_map(|start| (start + 1..=len).map(move |end| start..end))
+                .filter(|range| {
+                    safe(range.clone())
+                        && (range.start == 0 || !safe(range.start - 1..range.end))
+                        && (range.end == len || !safe(range.start..range.end + 1))
+                })
+                .collect::<Vec<_>>();
+            let found = policy
+                .segments(&level)
+                .into_iter()
+                .map(|segment| segment.range)
+                .collect::<Vec<_>>();
+            assert_eq!(found, expected, "{level:?} {policy:?}");
+        }
+    }
+
+    #[test]
+    fn long_safe_stretch() {
+        let level = (0..200_000u32).map(|level| level * 2).collect::<Vec<_>>();
+        assert_eq!(
+            segments(&level),
+            vec![segment(Some(Direction::Ascending), 0..level.len())]
+        );
+    }
+}
@@ -0,0 +1,204 @@
+use std::{fmt::Debug, io::BufRead, marker::PhantomData, str::FromStr};
+
+use crate::{error::StreamError, parser::parse_report, Level, SafetyPolicy, Verdict};
+
+/// Running counts of how many reports were found safe
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Tally {
+    /// Reports that are safe as they are
+    pub safe: usize,
+    /// Reports that are only safe with the problem dampener
+    pub dampened: usize,
+    pub unsafe_reports: usize,
+}
+
+impl Tally {
+    pub fn record(&mut self, verdict: &Verdict) {
+        match verdict {
+            Verdict::Safe => self.safe += 1,
+            Verdict::Dampened { .. } => self.dampened += 1,
+            Verdict::Unsafe(_) => self.unsafe_reports += 1,
+        }
+    }
+
+    /// How many reports are safe with the problem dampener, including those that don't need it
+    pub fn safe_with_dampener(&self) -> usize {
+        self.safe + self.dampened
+    }
+
+    pub fn total(&self) -> usize {
+        self.safe + self.dampened + self.unsafe_reports
+    }
+}
+
+/// Classifies reports as they are read from `reader`, one line at a time, so only the report being
+/// checked is ever held in memory.
+///
+/// A line is only classified once its newline has been read, so a reader over a file that is still
+/// being appended to can be polled again later to pick up where it left off. Blank lines are
+/// skipped.
+#[derive(Debug)]
+pub struct Classifier<R, T> {
+    reader: R,
+    policy: SafetyPolicy,
+    tally: Tally,
+    /// The line being read, which may not be complete yet
+    line: String,
+    line_number: usize,
+    levels: PhantomData<T>,
+}
+
+impl<R, T> Classifier<R, T>
+where
+    R: BufRead,
+    T: Level + FromStr,
+    <T as FromStr>::Err: Debug,
+{
+    pub fn new(reader: R, policy: SafetyPolicy) -> Self {
+        Classifier {
+            reader,
+            policy,
+            tally: Tally::default(),
+            line: String::new(),
+            line_number: 0,
+            levels: PhantomData,
+        }
+    }
+
+    pub fn tally(&self) -> Tally {
+        self.tally
+    }
+
+    /// Classifies every complete line that can be read right now, returning the counts so far.
+    ///
+    /// If a line fails to parse, it isn't counted and the error is returned, but the classifier can
+    /// carry on from the next line.
+    pub fn poll(&mut self) -> Result<Tally, StreamError<T::Err>> {
+        loop {
+            if self.reader.read_line(&mut self.line)? == 0 || !self.line.ends_with('\n') {
+                return Ok(self.tally);
+            }
+            self.classify_line()?;
+        }
+    }
+
+    /// Classifies everything left in the reader, including a last line without a newline
+    pub fn finish(mut self) -> Result<Tally, StreamError<T::Err>> {
+        self.poll()?;
+        if !self.line.is_empty() {
+            self.classify_line()?;
+        }
+        Ok(self.tally)
+    }
+
+    fn classify_line(&mut self) -> Result<(), StreamError<T::Err>> {
+        self.line_number += 1;
+        let line = std::mem::take(&mut self.line);
+        if line.trim().is_empty() {
+            return Ok(());
+        }
+        let level: Vec<T> = parse_report(self.line_number, &line)?;
+        self.tally
+            .record(&self.policy.check_report_with_problem_dampener(&level));
+        Ok(())
+    }
+}
+
+/// Counts the safe, dampened and unsafe reports in `reader` with a [`Classifier`]
+pub fn classify_reports<R, T>(reader: R, policy: SafetyPolicy) -> Result<Tally, StreamError<T::Err>>
+where
+    R: BufRead,
+    T: Level + FromStr,
+    <T as FromStr>::Err: Debug,
+{
+    Classifier::<R, T>::new(reader, policy).finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufReader, Read};
+
+    use super::*;
+    use crate::error::ParseError;
+
+    const INPUT: &str = include_str!("test_input.txt");
+
+    #[test]
+    fn test_input() {
+        let tally = classify_reports::<_, u8>(INPUT.as_bytes(), SafetyPolicy::default()).unwrap();
+        assert_eq!(
+            tally,
+            Tally {
+                safe: 2,
+                dampened: 2,
+                unsafe_reports: 2
+            }
+        );
+        assert_eq!(tally.safe_with_dampener(), 4);
+        assert_eq!(tally.total(), 6);
+    }
+
+    /// A reader over a file that is still being written, which hands out what has been written so
+    /// far a few bytes at a time
+    struct Growing<'a> {
+        written: &'a std::cell::Cell<usize>,
+        data: &'a [u8],
+        read: usize,
+    }
+
+    impl Read for Growing<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let end = self.written.get().min(self.read + 3);
+            let n = buf.len().min(end - self.read);
+            buf[..n].copy_from_slice(&self.data[self.read..self.read + n]);
+            self.read += n;
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn appended_input() {
+        let data = b"7 6 4 2 1\n1 2 7 8 9\n\n1 3 2 4 5\n1 3 6 7 9";
+        let written = std::cell::Cell::new(0);
+        let reader = BufReader::with_capacity(
+            4,
+            Growing {
+                written: &written,
+                data,
+                read: 0,
+            },
+        );
+        let mut classifier = Classifier::<_, u8>::new(reader, SafetyPolicy::default());
+
+        // the first report is still being written
+        written.set(7);
+        assert_eq!(classifier.poll().unwrap().total(), 0);
+        written.set(25);
+        assert_eq!(
+            classifier.poll().unwrap(),
+            Tally {
+                safe: 1,
+                dampened: 0,
+                unsafe_reports: 1
+            }
+        );
+        written.set(data.len());
+        assert_eq!(classifier.poll().unwrap().dampened, 1);
+        assert_eq!(classifier.finish().unwrap().safe, 2);
+    }
+
+    #[test]
+    fn errors_resume() {
+        let mut classifier =
+            Classifier::<_, u8>::new("1 2 3\n1 2 300\n3 2 1\n".as_bytes(), Default::default());
+        assert!(matches!(
+            classifier.poll(),
+            Err(StreamError::Parse(ParseError::InvalidLevel {
+                line: 2,
+                column: 5,
+                ..
+            }))
+        ));
+        assert_eq!(classifier.poll().unwrap().safe, 2);
+    }
+}
@@ -0,0 +1,77 @@
+use crate::{Direction, Level, SafetyPolicy};
+
+/// The part of a report that can be trusted, as found by
+/// [`SafetyPolicy::longest_safe_subsequence`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SafeSubsequence<T> {
+    /// The indices of the levels that were kept, in order
+    pub kept: Vec<usize>,
+    /// The levels that were kept
+    pub levels: Vec<T>,
+    /// None if the kept levels have no steps between levels that differ, so could go either way
+    pub direction: Option<Direction>,
+}
+
+impl<T> SafeSubsequence<T> {
+    /// What share of the report was kept, from 0 to 1. An empty report is all kept.
+    pub fn kept_fraction(&self, report_len: usize) -> f64 {
+        if report_len == 0 {
+            1.0
+        } else {
+            self.kept.len() as f64 / report_len as f64
+        }
+    }
+}
+
+impl SafetyPolicy {
+    /// The longest (not necessarily contiguous) subsequence of the report that is safe on its own.
+    /// Removing all the levels not in it is the cheapest way to make the report safe, so this is
+    /// the problem dampener without a limit on how many levels it removes.
+    pub fn longest_safe_subsequence<T: Level>(&self, level: &[T]) -> SafeSubsequence<T> {
+        let kept = self.longest_safe_indices(level);
+        let levels = kept.iter().map(|&i| level[i]).collect::<Vec<_>>();
+        let direction = levels
+            .windows(2)
+            .find_map(|pair| Direction::from(pair[0], pair[1], self));
+        SafeSubsequence {
+            kept,
+            levels,
+            direction,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{longest_safe_subsequence, AllowedDirections};
+
+    #[test]
+    fn kept_levels() {
+        assert_eq!(
+            longest_safe_subsequence(&[9, 1, 2, 8, 3, 9, 4]),
+            SafeSubsequence {
+                kept: vec![1, 2, 4, 6],
+                levels: vec![1, 2, 3, 4],
+                direction: Some(Direction::Ascending),
+            }
+        );
+        let all = longest_safe_subsequence(&[7, 6, 4, 2, 1]);
+        assert_eq!(all.kept, vec![0, 1, 2, 3, 4]);
+        assert_eq!(all.direction, Some(Direction::Descending));
+        assert_eq!(all.kept_fraction(5), 1.0);
+
+        let one = longest_safe_subsequence(&[1, 10, 20]);
+        assert_eq!(one.kept.len(), 1);
+        assert_eq!(one.direction, None);
+        assert_eq!(longest_safe_subsequence::<u8>(&[]).kept_fraction(0), 1.0);
+
+        let falling = SafetyPolicy {
+            directions: AllowedDirections::Descending,
+            ..Default::default()
+        };
+        let kept = falling.longest_safe_subsequence(&[1, 3, 6, 7, 9, 8, 6]);
+        assert_eq!(kept.levels, vec![9, 8, 6]);
+        assert_eq!(kept.kept_fraction(7), 3.0 / 7.0);
+    }
+}
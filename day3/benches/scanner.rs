@@ -0,0 +1,65 @@
+#![feature(test)]
+
+extern crate test;
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use test::{Bencher, black_box};
+
+/// About a megabyte of corrupted memory, where `density` is the chance that any given piece of it
+/// is an instruction rather than garbage
+fn corrupted(density: f64) -> String {
+    let mut rng = StdRng::seed_from_u64(3);
+    let mut input = String::with_capacity(1 << 20);
+    while input.len() < 1 << 20 {
+        if rng.gen_bool(density) {
+            match rng.gen_range(0..4) {
+                0 => input.push_str("do()"),
+                1 => input.push_str("don't()"),
+                _ => input.push_str(&format!(
+                    "mul({},{})",
+                    rng.gen_range(0..1000),
+                    rng.gen_range(0..1000)
+                )),
+            }
+        } else {
+            // printable garbage, including the odd stray `m` or `d`
+            input.push(rng.gen_range(b'!'..=b'~') as char);
+        }
+    }
+    input
+}
+
+#[bench]
+fn nom_mostly_garbage(b: &mut Bencher) {
+    let input = corrupted(0.001);
+    b.bytes = input.len() as u64;
+    b.iter(|| day3::reference::parse(black_box(&input), true));
+}
+
+#[bench]
+fn memchr_mostly_garbage(b: &mut Bencher) {
+    let input = corrupted(0.001);
+    b.bytes = input.len() as u64;
+    b.iter(|| day3::parser::parse(black_box(&input), true));
+}
+
+#[bench]
+fn nom_dense(b: &mut Bencher) {
+    let input = corrupted(0.2);
+    b.bytes = input.len() as u64;
+    b.iter(|| day3::reference::parse(black_box(&input), true));
+}
+
+#[bench]
+fn memchr_dense(b: &mut Bencher) {
+    let input = corrupted(0.2);
+    b.bytes = input.len() as u64;
+    b.iter(|| day3::parser::parse(black_box(&input), true));
+}
+
+#[bench]
+fn parallel_dense(b: &mut Bencher) {
+    let input = corrupted(0.2);
+    b.bytes = input.len() as u64;
+    b.iter(|| day3::parallel::sum(black_box(&input), true));
+}
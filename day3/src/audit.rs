@@ -0,0 +1,234 @@
+use std::fmt::Write;
+
+use crate::{
+    Conditional, Mul,
+    parser::{self, ParsedAtom, Token},
+};
+
+/// Where something was found in the input. Lines and columns are 1-indexed, and columns count
+/// characters rather than bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    /// How many bytes into the input it starts
+    pub offset: usize,
+    /// How many bytes long it is
+    pub len: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// An instruction recognised in the input, along with where it was found and whether `Mul`s were
+/// enabled when it was reached. For a conditional, that's the state from before it takes effect.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SpannedAtom {
+    pub atom: ParsedAtom,
+    pub span: Span,
+    pub enabled: bool,
+}
+
+/// Every instruction in the input, in the same order as [`parser::parse`] finds them
+pub fn spanned_atoms(input: &str) -> Vec<SpannedAtom> {
+    let mut atoms = Vec::new();
+    let mut enabled = true;
+    let (mut line, mut column) = (1, 1);
+    for (range, token) in parser::tokens(input) {
+        if let Token::Atom(atom) = token {
+            let span = Span {
+                offset: range.start,
+                len: range.len(),
+                line,
+                column,
+            };
+            let enabled_after = match atom {
+                ParsedAtom::Conditional(conditional) => conditional == Conditional::Do,
+                ParsedAtom::Mul(_) => enabled,
+            };
+            atoms.push(SpannedAtom {
+                atom,
+                span,
+                enabled,
+            });
+            enabled = enabled_after;
+        }
+        for c in input[range].chars() {
+            if c == '\n' {
+                line += 1;
+                column = 1;
+            } else {
+                column += 1;
+            }
+        }
+    }
+    atoms
+}
+
+const ENABLED_MUL: &str = "\x1b[1;32m";
+const DISABLED_MUL: &str = "\x1b[9;31m";
+const DO: &str = "\x1b[1;36m";
+const DONT: &str = "\x1b[1;33m";
+const RESET: &str = "\x1b[0m";
+
+fn style(atom: &SpannedAtom) -> &'static str {
+    match atom.atom {
+        ParsedAtom::Mul(_) if atom.enabled => ENABLED_MUL,
+        ParsedAtom::Mul(_) => DISABLED_MUL,
+        ParsedAtom::Conditional(Conditional::Do) => DO,
+        ParsedAtom::Conditional(Conditional::Dont) => DONT,
+    }
+}
+
+/// The input with ANSI colours on every instruction: enabled `Mul`s in green, disabled ones struck
+/// through in red, `do()` in cyan and `don't()` in yellow
+pub fn highlight(input: &str, atoms: &[SpannedAtom]) -> String {
+    let mut highlighted = String::with_capacity(input.len() * 2);
+    let mut at = 0;
+    for atom in atoms {
+        let Span { offset, len, .. } = atom.span;
+        highlighted.push_str(&input[at..offset]);
+        highlighted.push_str(style(atom));
+        highlighted.push_str(&input[offset..offset + len]);
+        highlighted.push_str(RESET);
+        at = offset + len;
+    }
+    highlighted.push_str(&input[at..]);
+    highlighted
+}
+
+fn product(mul: &Mul) -> u64 {
+    u64::from(mul.x) * u64::from(mul.y)
+}
+
+/// The highlighted input, followed by a key to the colours and how the part 1 and part 2 sums
+/// compare
+pub fn report(input: &str) -> String {
+    let atoms = spanned_atoms(input);
+    let mut report = highlight(input, &atoms);
+    if !report.ends_with('\n') {
+        report.push('\n');
+    }
+    let (mut enabled, mut disabled) = ((0, 0), (0, 0));
+    for atom in &atoms {
+        if let ParsedAtom::Mul(mul) = &atom.atom {
+            let tally = if atom.enabled {
+                &mut enabled
+            } else {
+                &mut disabled
+            };
+            tally.0 += 1;
+            tally.1 += product(mul);
+        }
+    }
+    let toggles = atoms.len() - enabled.0 - disabled.0;
+    let _ = writeln!(
+        report,
+        "\n{ENABLED_MUL}enabled{RESET} {DISABLED_MUL}disabled{RESET} {DO}do(){RESET} {DONT}don't(){RESET}"
+    );
+    let _ = writeln!(report, "{toggles} conditionals");
+    let _ = writeln!(
+        report,
+        "{} enabled muls sum to {} (part 2)",
+        enabled.0, enabled.1
+    );
+    let _ = writeln!(
+        report,
+        "{} disabled muls sum to {}, the difference from part 1",
+        disabled.0, disabled.1
+    );
+    let _ = writeln!(
+        report,
+        "{} muls sum to {} (part 1)",
+        enabled.0 + disabled.0,
+        enabled.1 + disabled.1
+    );
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = include_str!("input.txt");
+    const TEST_INPUT: &str = include_str!("test_input.txt");
+
+    #[test]
+    fn spans() {
+        let atoms = spanned_atoms("ä mul(1,2)\n xdon't()mul(3,4)\ndo()");
+        let spans = atoms
+            .iter()
+            .map(|atom| (atom.span, atom.enabled))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            spans,
+            vec![
+                (
+                    Span {
+                        offset: 3,
+                        len: 8,
+                        line: 1,
+                        column: 3
+                    },
+                    true
+                ),
+                (
+                    Span {
+                        offset: 14,
+                        len: 7,
+                        line: 2,
+                        column: 3
+                    },
+                    true
+                ),
+                (
+                    Span {
+                        offset: 21,
+                        len: 8,
+                        line: 2,
+                        column: 10
+                    },
+                    false
+                ),
+                (
+                    Span {
+                        offset: 30,
+                        len: 4,
+                        line: 3,
+                        column: 1
+                    },
+                    false
+                ),
+            ]
+        );
+        assert_eq!(atoms[2].atom, ParsedAtom::Mul(Mul { x: 3, y: 4 }));
+    }
+
+    #[test]
+    fn matches_parse() {
+        for input in [TEST_INPUT, INPUT] {
+            let atoms = spanned_atoms(input);
+            for atom in &atoms {
+                let text = &input[atom.span.offset..atom.span.offset + atom.span.len];
+                let (rest, parsed) = crate::reference::atom(text).unwrap();
+                assert_eq!((rest, &parsed), ("", &atom.atom));
+            }
+            let enabled = atoms
+                .into_iter()
+                .filter_map(|atom| match atom.atom {
+                    ParsedAtom::Mul(mul) if atom.enabled => Some(mul),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(enabled, parser::parse(input, true).unwrap());
+        }
+    }
+
+    #[test]
+    fn report_test_input() {
+        let report = report(TEST_INPUT);
+        assert!(report.starts_with(&format!(
+            "x{ENABLED_MUL}mul(2,4){RESET}&mul[3,7]!^{DONT}don't(){RESET}_{DISABLED_MUL}mul(5,5){RESET}"
+        )));
+        assert!(report.contains("2 enabled muls sum to 48 (part 2)"));
+        assert!(report.contains("2 disabled muls sum to 113"));
+        assert!(report.contains("4 muls sum to 161 (part 1)"));
+    }
+}
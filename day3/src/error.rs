@@ -1,16 +1,39 @@
 use nom::error::ErrorKind;
 use thiserror::Error;
 
-#[derive(Debug, Error, PartialEq, Eq)]
+#[derive(Debug, Error)]
 pub enum ParseError {
     #[error("Nom error: {0:?}")]
-    Muls(String, ErrorKind)
+    Muls(String, ErrorKind),
+    #[error("no instructions found in the input")]
+    NoInstructions,
+    #[error("input ended part way through an instruction")]
+    Incomplete,
+    #[error("failed to read input: {0}")]
+    Io(#[from] std::io::Error)
 }
 
+/// `io::Error` can't be compared, so read errors are equal when they are the same kind of error
+impl PartialEq for ParseError {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Muls(input, kind), Self::Muls(other_input, other_kind)) => {
+                input == other_input && kind == other_kind
+            }
+            (Self::NoInstructions, Self::NoInstructions) => true,
+            (Self::Incomplete, Self::Incomplete) => true,
+            (Self::Io(e), Self::Io(other)) => e.kind() == other.kind(),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for ParseError {}
+
 impl From<nom::Err<nom::error::Error<&str>>> for ParseError {
     fn from(value: nom::Err<nom::error::Error<&str>>) -> Self {
         match value {
-            nom::Err::Incomplete(_) => unreachable!("we don't handle streaming data"),
+            nom::Err::Incomplete(_) => Self::Incomplete,
             nom::Err::Error(e) => Self::Muls(e.input.to_string(), e.code),
             nom::Err::Failure(e) => Self::Muls(e.input.to_string(), e.code),
         }
@@ -0,0 +1,291 @@
+/// The grammar of one argument: an integer in `min..=max`, written in decimal. A leading `-` is
+/// only recognised when `min` is negative.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Arg {
+    pub min: i64,
+    pub max: i64,
+}
+
+impl Arg {
+    pub fn unsigned(max: i64) -> Self {
+        Arg { min: 0, max }
+    }
+
+    pub fn signed(min: i64, max: i64) -> Self {
+        Arg { min, max }
+    }
+
+    /// Reads the argument from the start of `input`, returning it along with how many bytes it took
+    fn read(&self, input: &[u8]) -> Option<(i64, usize)> {
+        let negative = self.min < 0 && input.first() == Some(&b'-');
+        let sign_len = usize::from(negative);
+        let digits = input[sign_len..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count();
+        if digits == 0 {
+            return None;
+        }
+        let value = input[sign_len..sign_len + digits]
+            .iter()
+            .try_fold(0i64, |value, &digit| {
+                let digit = i64::from(digit - b'0');
+                value
+                    .checked_mul(10)?
+                    .checked_add(if negative { -digit } else { digit })
+            })?;
+        (self.min..=self.max)
+            .contains(&value)
+            .then_some((value, sign_len + digits))
+    }
+}
+
+/// Which groups of instructions a toggle turns on or off
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Scope {
+    All,
+    Groups(Vec<String>),
+}
+
+impl Scope {
+    pub fn contains(&self, group: &str) -> bool {
+        match self {
+            Scope::All => true,
+            Scope::Groups(groups) => groups.iter().any(|g| g == group),
+        }
+    }
+}
+
+/// What an instruction does when it is run
+#[derive(Debug, Clone)]
+pub enum Effect {
+    /// Computes a value from the arguments, which is added to the total if the instruction's group
+    /// is enabled
+    Value {
+        group: String,
+        eval: fn(&[i64]) -> i64,
+    },
+    /// Enables or disables every instruction in the scope, until another toggle says otherwise
+    Toggle { scope: Scope, enable: bool },
+}
+
+/// An instruction that can be recognised in corrupted memory: its name, then its arguments
+/// separated by commas in parentheses, like `mul(2,3)` or `do()`
+#[derive(Debug, Clone)]
+pub struct Instruction {
+    pub name: String,
+    pub args: Vec<Arg>,
+    pub effect: Effect,
+}
+
+impl Instruction {
+    pub fn value(name: &str, group: &str, args: Vec<Arg>, eval: fn(&[i64]) -> i64) -> Self {
+        Instruction {
+            name: name.to_string(),
+            args,
+            effect: Effect::Value {
+                group: group.to_string(),
+                eval,
+            },
+        }
+    }
+
+    pub fn toggle(name: &str, scope: Scope, enable: bool) -> Self {
+        Instruction {
+            name: name.to_string(),
+            args: Vec::new(),
+            effect: Effect::Toggle { scope, enable },
+        }
+    }
+
+    /// Reads the instruction from the start of `input`, returning its arguments along with how many
+    /// bytes it took
+    fn read(&self, input: &[u8]) -> Option<(Vec<i64>, usize)> {
+        let mut at = self.name.len();
+        if !input.starts_with(self.name.as_bytes()) || input.get(at) != Some(&b'(') {
+            return None;
+        }
+        at += 1;
+        let mut args = Vec::with_capacity(self.args.len());
+        for (idx, arg) in self.args.iter().enumerate() {
+            if idx > 0 {
+                if input.get(at) != Some(&b',') {
+                    return None;
+                }
+                at += 1;
+            }
+            let (value, len) = arg.read(&input[at..])?;
+            args.push(value);
+            at += len;
+        }
+        (input.get(at) == Some(&b')')).then_some((args, at + 1))
+    }
+}
+
+/// An instruction found in the input, by its index in the [`InstructionSet`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Atom {
+    pub instruction: usize,
+    pub args: Vec<i64>,
+}
+
+/// The instructions that can be recognised in corrupted memory
+#[derive(Debug, Clone, Default)]
+pub struct InstructionSet {
+    instructions: Vec<Instruction>,
+}
+
+impl InstructionSet {
+    /// An empty set, which recognises nothing
+    pub fn new() -> Self {
+        InstructionSet::default()
+    }
+
+    /// The puzzle's instructions: `mul(x,y)` in the `mul` group, with `do()` and `don't()` turning
+    /// everything on and off
+    pub fn standard() -> Self {
+        InstructionSet::new()
+            .with(Instruction::toggle("don't", Scope::All, false))
+            .with(Instruction::toggle("do", Scope::All, true))
+            .with(Instruction::value(
+                "mul",
+                "mul",
+                vec![Arg::unsigned(u16::MAX.into()); 2],
+                |args| args[0] * args[1],
+            ))
+    }
+
+    /// Adds an instruction. Where two instructions could both be read at the same point, the one
+    /// added first wins.
+    pub fn with(mut self, instruction: Instruction) -> Self {
+        self.instructions.push(instruction);
+        self
+    }
+
+    pub fn get(&self, idx: usize) -> Option<&Instruction> {
+        self.instructions.get(idx)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Instruction> {
+        self.instructions.iter()
+    }
+
+    /// Every group a value instruction belongs to, without duplicates
+    pub fn groups(&self) -> Vec<&str> {
+        let mut groups = Vec::new();
+        for instruction in &self.instructions {
+            if let Effect::Value { group, .. } = &instruction.effect
+                && !groups.contains(&group.as_str())
+            {
+                groups.push(group.as_str());
+            }
+        }
+        groups
+    }
+
+    /// Every instruction in the input, in order. Anything that isn't an instruction is skipped
+    /// one byte at a time, so an instruction can start inside a broken one.
+    pub fn scan(&self, input: &str) -> Vec<Atom> {
+        let input = input.as_bytes();
+        let mut atoms = Vec::new();
+        let mut at = 0;
+        while at < input.len() {
+            match self.read(&input[at..]) {
+                Some((atom, len)) => {
+                    atoms.push(atom);
+                    at += len;
+                }
+                None => at += 1,
+            }
+        }
+        atoms
+    }
+
+    fn read(&self, input: &[u8]) -> Option<(Atom, usize)> {
+        self.instructions
+            .iter()
+            .enumerate()
+            .find_map(|(idx, instruction)| {
+                let (args, len) = instruction.read(input)?;
+                Some((
+                    Atom {
+                        instruction: idx,
+                        args,
+                    },
+                    len,
+                ))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arguments() {
+        let byte = Arg::unsigned(255);
+        assert_eq!(byte.read(b"12,3"), Some((12, 2)));
+        assert_eq!(byte.read(b"0255)"), Some((255, 4)));
+        assert_eq!(byte.read(b"256"), None);
+        assert_eq!(byte.read(b"-1"), None);
+        assert_eq!(byte.read(b"99999999999999999999"), None);
+        assert_eq!(Arg::signed(-10, 10).read(b"-10)"), Some((-10, 3)));
+        assert_eq!(Arg::signed(-10, 10).read(b"-)"), None);
+    }
+
+    #[test]
+    fn scan_standard() {
+        let set = InstructionSet::standard();
+        let atoms =
+            set.scan("xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))");
+        let names = atoms
+            .iter()
+            .map(|atom| set.get(atom.instruction).unwrap().name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["mul", "don't", "mul", "mul", "do", "mul"]);
+        assert_eq!(atoms[3].args, vec![11, 8]);
+        assert_eq!(set.groups(), vec!["mul"]);
+    }
+
+    #[test]
+    fn scan_custom() {
+        let set = InstructionSet::standard()
+            .with(Instruction::value(
+                "add",
+                "add",
+                vec![Arg::signed(-999, 999); 2],
+                |args| args[0] + args[1],
+            ))
+            .with(Instruction::value(
+                "neg",
+                "add",
+                vec![Arg::signed(-999, 999)],
+                |args| -args[0],
+            ))
+            .with(Instruction::toggle(
+                "noadd",
+                Scope::Groups(vec!["add".to_string()]),
+                false,
+            ));
+        let atoms = set.scan("add(-3,4)neg(5)neg()noadd()mul(1,-2)");
+        assert_eq!(
+            atoms,
+            vec![
+                Atom {
+                    instruction: 3,
+                    args: vec![-3, 4]
+                },
+                Atom {
+                    instruction: 4,
+                    args: vec![5]
+                },
+                Atom {
+                    instruction: 5,
+                    args: vec![]
+                },
+            ]
+        );
+        assert_eq!(set.groups(), vec!["mul", "add"]);
+    }
+}
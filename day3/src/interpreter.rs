@@ -0,0 +1,141 @@
+use crate::instruction::{Atom, Effect, InstructionSet};
+
+/// Runs recognised instructions in order, keeping track of which groups are enabled and what each
+/// group has added up to
+#[derive(Debug, Clone)]
+pub struct Interpreter<'a> {
+    set: &'a InstructionSet,
+    /// Each group, whether it is enabled, and its total so far
+    groups: Vec<(&'a str, bool, i128)>,
+}
+
+impl<'a> Interpreter<'a> {
+    /// An interpreter with every group enabled and nothing added up yet
+    pub fn new(set: &'a InstructionSet) -> Self {
+        Interpreter {
+            set,
+            groups: set
+                .groups()
+                .into_iter()
+                .map(|group| (group, true, 0))
+                .collect(),
+        }
+    }
+
+    /// Runs one instruction. Atoms that don't belong to this interpreter's set are ignored.
+    pub fn step(&mut self, atom: &Atom) {
+        let Some(instruction) = self.set.get(atom.instruction) else {
+            return;
+        };
+        match &instruction.effect {
+            Effect::Value { group, eval } => {
+                if let Some((_, true, total)) = self.groups.iter_mut().find(|(g, ..)| g == group) {
+                    *total += i128::from(eval(&atom.args));
+                }
+            }
+            Effect::Toggle { scope, enable } => {
+                for (group, enabled, _) in &mut self.groups {
+                    if scope.contains(group) {
+                        *enabled = *enable;
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn run<'b>(&mut self, atoms: impl IntoIterator<Item = &'b Atom>) {
+        for atom in atoms {
+            self.step(atom);
+        }
+    }
+
+    pub fn is_enabled(&self, group: &str) -> bool {
+        self.groups
+            .iter()
+            .any(|&(g, enabled, _)| g == group && enabled)
+    }
+
+    /// What the enabled instructions of one group have added up to
+    pub fn group_total(&self, group: &str) -> i128 {
+        self.groups
+            .iter()
+            .find(|&&(g, ..)| g == group)
+            .map_or(0, |&(_, _, total)| total)
+    }
+
+    /// What the enabled instructions of every group have added up to
+    pub fn total(&self) -> i128 {
+        self.groups.iter().map(|&(_, _, total)| total).sum()
+    }
+}
+
+/// Scans the input with the instruction set, runs everything it finds, and returns the total
+pub fn evaluate(set: &InstructionSet, input: &str) -> i128 {
+    let mut interpreter = Interpreter::new(set);
+    interpreter.run(&set.scan(input));
+    interpreter.total()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::{Arg, Instruction, Scope};
+
+    const INPUT: &str = include_str!("input.txt");
+
+    #[test]
+    fn matches_parse() {
+        for input in [INPUT, include_str!("test_input.txt")] {
+            let part2: u64 = crate::parser::parse(input, true)
+                .unwrap()
+                .into_iter()
+                .map(u64::from)
+                .sum();
+            assert_eq!(evaluate(&InstructionSet::standard(), input), part2.into());
+
+            let part1: u64 = crate::parser::parse(input, false)
+                .unwrap()
+                .into_iter()
+                .map(u64::from)
+                .sum();
+            let unconditional =
+                InstructionSet::new().with(InstructionSet::standard().get(2).unwrap().clone());
+            assert_eq!(evaluate(&unconditional, input), part1.into());
+        }
+    }
+
+    #[test]
+    fn groups_toggle_separately() {
+        let set = InstructionSet::standard()
+            .with(Instruction::value(
+                "add",
+                "add",
+                vec![Arg::signed(-999, 999); 2],
+                |args| args[0] + args[1],
+            ))
+            .with(Instruction::value(
+                "neg",
+                "add",
+                vec![Arg::signed(-999, 999)],
+                |args| -args[0],
+            ))
+            .with(Instruction::toggle(
+                "addoff",
+                Scope::Groups(vec!["add".to_string()]),
+                false,
+            ))
+            .with(Instruction::toggle(
+                "addon",
+                Scope::Groups(vec!["add".to_string()]),
+                true,
+            ));
+        let mut interpreter = Interpreter::new(&set);
+        interpreter.run(&set.scan(
+            "mul(2,3)add(1,-10)addoff()add(100,100)mul(1,1)don't()mul(5,5)addon()neg(-4)do()mul(2,2)",
+        ));
+        assert_eq!(interpreter.group_total("mul"), 6 + 1 + 4);
+        assert_eq!(interpreter.group_total("add"), -9 + 4);
+        assert_eq!(interpreter.total(), 6);
+        assert!(interpreter.is_enabled("add"));
+    }
+}
@@ -1,8 +1,13 @@
-#![feature(assert_matches)]
+pub mod audit;
+pub mod instruction;
+pub mod interpreter;
+pub mod parallel;
 pub mod parser;
-mod error;
+pub mod reference;
+pub mod stream;
+pub mod error;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Mul {
     pub x: u16,
     pub y: u16
@@ -14,7 +19,7 @@ impl From<Mul> for u64 {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Conditional {
     Do,
     Dont
@@ -5,6 +5,10 @@ fn solve(muls: Vec<day3::Mul>) -> u64 {
 }
 
 fn main() {
+    if std::env::args().any(|arg| arg == "--report") {
+        print!("{}", day3::audit::report(INPUT));
+        return;
+    }
     let parsed: Vec<day3::Mul> = day3::parser::parse(INPUT, false).expect("Input must parse");
     let part1 = solve(parsed);
     let parsed2: Vec<day3::Mul> = day3::parser::parse(INPUT, true).expect("Input must parse");
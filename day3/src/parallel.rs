@@ -0,0 +1,162 @@
+use std::{num::NonZeroUsize, ops::Range, thread};
+
+use crate::{
+    Conditional,
+    parser::{ParsedAtom, atoms_starting_in},
+};
+
+/// What one chunk of the input adds up to, worked out without knowing what came before it
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct ChunkSums {
+    /// Every `Mul`, as for part 1
+    all: u64,
+    /// The enabled `Mul`s if the chunk starts enabled
+    if_enabled: u64,
+    /// The enabled `Mul`s if the chunk starts disabled
+    if_disabled: u64,
+    /// The last conditional in the chunk, which decides how the next chunk starts
+    last: Option<Conditional>,
+}
+
+impl ChunkSums {
+    fn scan(input: &str, range: Range<usize>) -> Self {
+        let mut sums = ChunkSums::default();
+        // whether Muls are enabled, given each starting state
+        let (mut from_enabled, mut from_disabled) = (true, false);
+        for (_, atom) in atoms_starting_in(input, range) {
+            match atom {
+                ParsedAtom::Mul(mul) => {
+                    let product = u64::from(mul);
+                    sums.all += product;
+                    if from_enabled {
+                        sums.if_enabled += product;
+                    }
+                    if from_disabled {
+                        sums.if_disabled += product;
+                    }
+                }
+                ParsedAtom::Conditional(conditional) => {
+                    from_enabled = conditional == Conditional::Do;
+                    from_disabled = from_enabled;
+                    sums.last = Some(conditional);
+                }
+            }
+        }
+        sums
+    }
+}
+
+/// Sums the `Mul`s in the input like [`crate::parser::parse`] and summing the result would, split
+/// into `chunks` scans.
+///
+/// Each chunk is scanned as though it started enabled and as though it started disabled, so no
+/// chunk has to wait for the ones before it. The chunks are then stitched together in order, each
+/// one's last conditional picking which of the next chunk's sums counts.
+///
+/// `chunks` only decides where the input is split. The chunks are shared out in contiguous
+/// batches between at most one thread per core.
+pub fn sum_in_chunks(input: &str, use_conditionals: bool, chunks: usize) -> u64 {
+    let chunk_size = input.len().div_ceil(chunks.max(1)).max(1);
+    let ranges = (0..input.len())
+        .step_by(chunk_size)
+        .map(|start| start..(start + chunk_size).min(input.len()))
+        .collect::<Vec<_>>();
+    let batch_size = ranges.len().div_ceil(cores()).max(1);
+    let sums = thread::scope(|scope| {
+        let handles = ranges
+            .chunks(batch_size)
+            .map(|batch| {
+                scope.spawn(move || {
+                    batch
+                        .iter()
+                        .map(|range| ChunkSums::scan(input, range.clone()))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("scanning a chunk can't panic"))
+            .collect::<Vec<_>>()
+    });
+
+    if !use_conditionals {
+        return sums.iter().map(|sums| sums.all).sum();
+    }
+    let mut enabled = true;
+    let mut total = 0;
+    for chunk in sums {
+        total += if enabled {
+            chunk.if_enabled
+        } else {
+            chunk.if_disabled
+        };
+        enabled = chunk.last.map_or(enabled, |last| last == Conditional::Do);
+    }
+    total
+}
+
+fn cores() -> usize {
+    thread::available_parallelism().map_or(1, NonZeroUsize::get)
+}
+
+/// [`sum_in_chunks`] with one chunk for each core
+pub fn sum(input: &str, use_conditionals: bool) -> u64 {
+    sum_in_chunks(input, use_conditionals, cores())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = include_str!("input.txt");
+    const TEST_INPUT: &str = include_str!("test_input.txt");
+
+    fn sequential(input: &str, use_conditionals: bool) -> u64 {
+        crate::parser::parse(input, use_conditionals)
+            .unwrap_or_default()
+            .into_iter()
+            .map(u64::from)
+            .sum()
+    }
+
+    #[test]
+    fn every_split_of_test_input() {
+        for use_conditionals in [false, true] {
+            let expected = sequential(TEST_INPUT, use_conditionals);
+            for chunks in 1..=TEST_INPUT.len() + 1 {
+                assert_eq!(
+                    sum_in_chunks(TEST_INPUT, use_conditionals, chunks),
+                    expected,
+                    "{chunks} chunks"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn matches_parse() {
+        assert_eq!(sum(INPUT, false), sequential(INPUT, false));
+        assert_eq!(sum(INPUT, true), sequential(INPUT, true));
+        for chunks in [2, 3, 16, 333] {
+            assert_eq!(sum_in_chunks(INPUT, true, chunks), sequential(INPUT, true));
+        }
+        assert_eq!(sum("", true), 0);
+        // a chunk with no conditionals leaves the state as the chunk before it did
+        let input = "don't()mul(1,1)xxxxxxxxmul(2,2)xxxxxxxxdo()mul(3,3)";
+        for chunks in 1..=input.len() {
+            assert_eq!(sum_in_chunks(input, true, chunks), 9, "{chunks} chunks");
+        }
+    }
+
+    #[test]
+    fn more_chunks_than_threads() {
+        // one chunk per byte, far more than there are threads to run them
+        for use_conditionals in [false, true] {
+            assert_eq!(
+                sum_in_chunks(INPUT, use_conditionals, INPUT.len()),
+                sequential(INPUT, use_conditionals)
+            );
+        }
+    }
+}
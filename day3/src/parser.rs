@@ -1,88 +1,182 @@
-use nom::{
-    IResult,
-    branch::alt,
-    bytes::complete::tag,
-    character::complete::{anychar, char, digit1},
-    combinator::{all_consuming, map, map_res, rest, value},
-    multi::{many_till, many1},
-    sequence::{delimited, preceded, separated_pair},
-};
-
-#[derive(Debug)]
-enum ParsedAtom {
+use std::{iter::Peekable, ops::Range};
+
+use memchr::memchr2;
+
+use crate::{Conditional, Mul, error::ParseError};
+
+/// An instruction recognised in corrupted memory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParsedAtom {
     Conditional(crate::Conditional),
     Mul(crate::Mul),
 }
 
-fn mul(input: &str) -> IResult<&str, crate::Mul> {
-    map(
-        preceded(
-            tag("mul"),
-            delimited(
-                char('('),
-                separated_pair(
-                    map_res(digit1, str::parse),
-                    char(','),
-                    map_res(digit1, str::parse),
-                ),
-                char(')'),
-            ),
-        ),
-        |(x, y)| crate::Mul { x, y },
-    )(input)
+/// Reads a `u16` in decimal from the start of `input`, as `digit1` followed by `str::parse` would,
+/// returning it along with how many bytes it took
+fn number(input: &[u8]) -> Option<(u16, usize)> {
+    let digits = input.iter().take_while(|b| b.is_ascii_digit()).count();
+    if digits == 0 {
+        return None;
+    }
+    let value = input[..digits].iter().try_fold(0u16, |value, &digit| {
+        value.checked_mul(10)?.checked_add(u16::from(digit - b'0'))
+    })?;
+    Some((value, digits))
 }
 
-fn conditional(input: &str) -> IResult<&str, crate::Conditional> {
-    alt((
-        value(crate::Conditional::Dont, tag("don't()")),
-        value(crate::Conditional::Do, tag("do()")),
-    ))(input)
+/// Reads `mul(x,y)` from the start of `input`, returning it along with how many bytes it took
+fn mul(input: &[u8]) -> Option<(Mul, usize)> {
+    let rest = input.strip_prefix(b"mul(")?;
+    let (x, x_len) = number(rest)?;
+    let rest = rest[x_len..].strip_prefix(b",")?;
+    let (y, y_len) = number(rest)?;
+    rest[y_len..].strip_prefix(b")")?;
+    Some((Mul { x, y }, 4 + x_len + 1 + y_len + 1))
 }
 
-fn atoms(input: &str) -> IResult<&str, Vec<ParsedAtom>> {
-    let mul_parser = map(mul, ParsedAtom::Mul);
-    let conditional_parser = map(conditional, ParsedAtom::Conditional);
-    let (input, many_results) =
-        many1(many_till(anychar, alt((conditional_parser, mul_parser))))(input)?;
-    let atoms = many_results.into_iter().map(|(_, atom)| atom).collect();
-    Ok((input, atoms))
+fn conditional(input: &[u8]) -> Option<(Conditional, usize)> {
+    if input.starts_with(b"don't()") {
+        Some((Conditional::Dont, 7))
+    } else if input.starts_with(b"do()") {
+        Some((Conditional::Do, 4))
+    } else {
+        None
+    }
 }
 
-pub fn parse(
-    input: &str,
-    use_conditionals: bool,
-) -> Result<Vec<crate::Mul>, crate::error::ParseError> {
-    let (input, atoms) = atoms(input)?;
-    let _ = all_consuming(rest)(input)?;
-    let mut muls: Vec<crate::Mul> = Vec::new();
-    let mut r#do = true;
-    for atom in atoms {
-        if use_conditionals {
-            match (r#do, atom) {
-                (true, ParsedAtom::Mul(mul)) => muls.push(mul),
-                (_, ParsedAtom::Conditional(crate::Conditional::Dont)) => r#do = false,
-                (_, ParsedAtom::Conditional(crate::Conditional::Do)) => r#do = true,
-                (false, ParsedAtom::Mul(_)) => {}
+/// Reads a single instruction from the start of `input`, returning it along with how many bytes
+/// it took
+fn atom(input: &[u8]) -> Option<(ParsedAtom, usize)> {
+    match input.first()? {
+        b'm' => mul(input).map(|(mul, len)| (ParsedAtom::Mul(mul), len)),
+        b'd' => conditional(input)
+            .map(|(conditional, len)| (ParsedAtom::Conditional(conditional), len)),
+        _ => None,
+    }
+}
+
+/// The instructions in the input, each with the byte range it covers, found by jumping straight
+/// to each `m` or `d` with [`memchr2`] and only then checking whether an instruction starts there
+#[derive(Debug, Clone)]
+pub struct Atoms<'a> {
+    input: &'a [u8],
+    pos: usize,
+    /// Only instructions starting before this are found, though they may run past it
+    end: usize,
+}
+
+pub fn atoms(input: &str) -> Atoms<'_> {
+    atoms_starting_in(input, 0..input.len())
+}
+
+/// The instructions that start within `range` of the input.
+///
+/// No instruction can start inside another one, so these are exactly the instructions a scan of
+/// the whole input would find there, whatever came before.
+pub fn atoms_starting_in(input: &str, range: Range<usize>) -> Atoms<'_> {
+    Atoms {
+        input: input.as_bytes(),
+        pos: range.start,
+        end: range.end.min(input.len()),
+    }
+}
+
+impl Iterator for Atoms<'_> {
+    type Item = (Range<usize>, ParsedAtom);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let start = self.pos + memchr2(b'm', b'd', self.input.get(self.pos..self.end)?)?;
+            match atom(&self.input[start..]) {
+                Some((atom, len)) => {
+                    self.pos = start + len;
+                    return Some((start..self.pos, atom));
+                }
+                // nothing else can start with this byte, so search on from the next one
+                None => self.pos = start + 1,
             }
-        } else if let ParsedAtom::Mul(mul) = atom {
-            muls.push(mul)
         }
     }
-    Ok(muls)
+}
+
+/// The `Mul`s in the input, in order. With `use_conditionals`, those after a `don't()` are skipped
+/// until the next `do()`. Input without any instructions at all is an error.
+pub fn parse(input: &str, use_conditionals: bool) -> Result<Vec<Mul>, ParseError> {
+    let mut found = false;
+    let mut enabled = true;
+    let muls = atoms(input)
+        .filter_map(|(_, atom)| {
+            found = true;
+            match atom {
+                ParsedAtom::Mul(mul) if enabled || !use_conditionals => Some(mul),
+                ParsedAtom::Mul(_) => None,
+                ParsedAtom::Conditional(conditional) => {
+                    enabled = conditional == Conditional::Do;
+                    None
+                }
+            }
+        })
+        .collect();
+    if found {
+        Ok(muls)
+    } else {
+        Err(ParseError::NoInstructions)
+    }
+}
+
+/// A piece of corrupted memory, as found by [`tokens`]
+#[derive(Debug, PartialEq, Eq)]
+pub enum Token {
+    Atom(ParsedAtom),
+    /// A run of input that isn't part of any instruction
+    Garbage,
+}
+
+/// An iterator over everything in the input, split into instructions and the garbage between
+/// them. Each token comes with the byte range it covers, and together they cover all of the input.
+#[derive(Debug, Clone)]
+pub struct Tokens<'a> {
+    atoms: Peekable<Atoms<'a>>,
+    /// How far into the input the next token starts
+    pos: usize,
+    len: usize,
+}
+
+/// Splits the input into tokens. Unlike [`parse`], this doesn't decide what the instructions
+/// mean, so `do()` and `don't()` can be given whatever rules the caller likes.
+pub fn tokens(input: &str) -> Tokens<'_> {
+    Tokens {
+        atoms: atoms(input).peekable(),
+        pos: 0,
+        len: input.len(),
+    }
+}
+
+impl Iterator for Tokens<'_> {
+    type Item = (Range<usize>, Token);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.pos;
+        if start == self.len {
+            return None;
+        }
+        if let Some((range, atom)) = self.atoms.next_if(|(range, _)| range.start == start) {
+            self.pos = range.end;
+            return Some((range, Token::Atom(atom)));
+        }
+        // the garbage runs up to the next instruction
+        self.pos = self.atoms.peek().map_or(self.len, |(range, _)| range.start);
+        Some((start..self.pos, Token::Garbage))
+    }
 }
 
 #[cfg(test)]
 mod error {
-    use std::assert_matches::assert_matches;
+    use std::assert_matches;
 
     use super::*;
     use crate::Mul;
     #[test]
-    fn parse_mul() {
-        let input = "mul(1,2)";
-        assert_eq!(mul(input), Ok(("", Mul { x: 1, y: 2 })))
-    }
-    #[test]
     fn parse_muls_with_extras() {
         let input = "abcmul(123,456)xyzmul(21,1)123";
         assert_eq!(
@@ -91,18 +185,103 @@ mod error {
         )
     }
     #[test]
-    fn parse_conditional() {
-        let input = "don't()do()";
-        assert_matches!(conditional(input), Ok(("do()", crate::Conditional::Dont)))
+    fn tokenize() {
+        let input = "ädon't()mul(1,2)xmul(3,4]do()";
+        let found = tokens(input).collect::<Vec<_>>();
+        assert_eq!(found, vec![
+            (0..2, Token::Garbage),
+            (2..9, Token::Atom(ParsedAtom::Conditional(crate::Conditional::Dont))),
+            (9..17, Token::Atom(ParsedAtom::Mul(Mul { x: 1, y: 2 }))),
+            (17..26, Token::Garbage),
+            (26..30, Token::Atom(ParsedAtom::Conditional(crate::Conditional::Do))),
+        ]);
+        assert_eq!(tokens("").next(), None);
+    }
+    #[test]
+    fn nested_toggles() {
+        // a rule parse can't express: each don't() needs its own do() to undo it
+        let input = "mul(1,1)don't()don't()mul(2,2)do()mul(3,3)do()mul(4,4)xx";
+        let mut depth = 0usize;
+        let mut sum = 0;
+        let mut garbage = 0;
+        for (range, token) in tokens(input) {
+            match token {
+                Token::Atom(ParsedAtom::Conditional(crate::Conditional::Dont)) => depth += 1,
+                Token::Atom(ParsedAtom::Conditional(crate::Conditional::Do)) => {
+                    depth = depth.saturating_sub(1)
+                }
+                Token::Atom(ParsedAtom::Mul(mul)) if depth == 0 => sum += u64::from(mul),
+                Token::Atom(ParsedAtom::Mul(_)) => {}
+                Token::Garbage => garbage += range.len(),
+            }
+        }
+        assert_eq!(sum, 1 + 16);
+        assert_eq!(garbage, 2);
     }
     #[test]
     fn parse_atoms() {
         let input = "adon't()bdo()cmul(21,44)";
-        let (_, result) = atoms(input).unwrap();
+        let result = atoms(input).map(|(_, atom)| atom).collect::<Vec<_>>();
         assert_matches!(result.as_slice(), [
             ParsedAtom::Conditional(crate::Conditional::Dont),
             ParsedAtom::Conditional(crate::Conditional::Do),
             ParsedAtom::Mul(Mul { x: 21, y: 44 })
         ])
     }
+    #[test]
+    fn numbers() {
+        assert_eq!(number(b"65535)"), Some((65535, 5)));
+        assert_eq!(number(b"00012,"), Some((12, 5)));
+        assert_eq!(number(b"65536"), None);
+        assert_eq!(number(b"x1"), None);
+        assert_eq!(
+            parse("mul(1,2)mul(65536,1)mul(3,4)", false).unwrap(),
+            vec![Mul { x: 1, y: 2 }, Mul { x: 3, y: 4 }]
+        );
+        assert_matches!(parse("mul(1,2", false), Err(ParseError::NoInstructions));
+    }
+    #[test]
+    fn matches_nom_parser() {
+        use rand::{Rng, SeedableRng, rngs::StdRng, seq::SliceRandom};
+
+        /// Pieces of instructions, broken and whole, to build corrupted memory from
+        const FRAGMENTS: &[&str] = &[
+            "mul(", "mul", "mu", "m", "do()", "don't()", "do(", "don't", "d", "(", ")", ",", "1",
+            "23", "456", "65535", "65536", "0000", "99999999999", "x", " ", "\n", "ä", "mul(1,2)",
+            "]",
+        ];
+
+        let mut rng = StdRng::seed_from_u64(24);
+        let inputs = (0..5_000)
+            .map(|_| {
+                let fragments = rng.gen_range(0..40);
+                (0..fragments)
+                    .map(|_| *FRAGMENTS.choose(&mut rng).unwrap())
+                    .collect::<String>()
+            })
+            .chain([
+                include_str!("input.txt").to_string(),
+                include_str!("test_input.txt").to_string(),
+            ]);
+        for input in inputs {
+            // every instruction the nom grammar reads, trying each character in turn
+            let mut expected = Vec::new();
+            let mut pos = 0;
+            while let Some(c) = input[pos..].chars().next() {
+                match crate::reference::atom(&input[pos..]) {
+                    Ok((rest, atom)) => {
+                        let end = input.len() - rest.len();
+                        expected.push((pos..end, atom));
+                        pos = end;
+                    }
+                    Err(_) => pos += c.len_utf8(),
+                }
+            }
+            assert_eq!(atoms(&input).collect::<Vec<_>>(), expected, "{input:?}");
+            for use_conditionals in [false, true] {
+                let expected = crate::reference::parse(&input, use_conditionals).ok();
+                assert_eq!(parse(&input, use_conditionals).ok(), expected, "{input:?}");
+            }
+        }
+    }
 }
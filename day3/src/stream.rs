@@ -0,0 +1,312 @@
+use std::io::{self, Read};
+
+use memchr::memchr2;
+
+use crate::{Conditional, Mul, error::ParseError, parser::ParsedAtom};
+
+/// How much is read from the underlying reader at a time, unless told otherwise
+const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// The fixed parts of an instruction, matched a byte at a time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Word {
+    /// `mul(`, before the arguments
+    Mul,
+    Do,
+    Dont,
+}
+
+impl Word {
+    fn text(self) -> &'static [u8] {
+        match self {
+            Word::Mul => b"mul(",
+            Word::Do => b"do()",
+            Word::Dont => b"don't()",
+        }
+    }
+}
+
+/// How much of an instruction has been read so far, which is all that needs to be kept between
+/// chunks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Not part way through anything that could be an instruction
+    Start,
+    /// This many bytes of the word have been read
+    Word(Word, usize),
+    /// Part way through the first argument of a `mul`, with its value so far, or `None` before
+    /// its first digit
+    X(Option<u16>),
+    /// Part way through the second argument of a `mul`, after the first one
+    Y(u16, Option<u16>),
+}
+
+impl State {
+    /// What can be read from `byte` when not part way through anything
+    fn start(byte: u8) -> Self {
+        match byte {
+            b'm' => State::Word(Word::Mul, 1),
+            b'd' => State::Word(Word::Do, 1),
+            _ => State::Start,
+        }
+    }
+
+    /// Reads one more byte, returning where that leaves things along with the instruction it
+    /// finished, if any.
+    ///
+    /// When `byte` can't carry on what has been read so far, it is read again from the start. No
+    /// instruction can start inside another one, so nothing before it needs to be.
+    fn step(self, byte: u8) -> (Self, Option<ParsedAtom>) {
+        match (self, byte) {
+            (State::Word(word, read), _) if word.text()[read] == byte => {
+                if read + 1 < word.text().len() {
+                    return (State::Word(word, read + 1), None);
+                }
+                match word {
+                    Word::Mul => (State::X(None), None),
+                    Word::Do => (State::Start, Some(ParsedAtom::Conditional(Conditional::Do))),
+                    Word::Dont => (
+                        State::Start,
+                        Some(ParsedAtom::Conditional(Conditional::Dont)),
+                    ),
+                }
+            }
+            (State::Word(Word::Do, 2), b'n') => (State::Word(Word::Dont, 3), None),
+            (State::X(x), b'0'..=b'9') => match number(x, byte) {
+                Some(x) => (State::X(Some(x)), None),
+                None => (State::start(byte), None),
+            },
+            (State::X(Some(x)), b',') => (State::Y(x, None), None),
+            (State::Y(x, y), b'0'..=b'9') => match number(y, byte) {
+                Some(y) => (State::Y(x, Some(y)), None),
+                None => (State::start(byte), None),
+            },
+            (State::Y(x, Some(y)), b')') => (State::Start, Some(ParsedAtom::Mul(Mul { x, y }))),
+            _ => (State::start(byte), None),
+        }
+    }
+}
+
+/// Adds another decimal digit to a `u16` being read, or `None` once it no longer fits. Leading
+/// zeros are fine, as with `str::parse`, so a run of digits of any length can still be read.
+fn number(value: Option<u16>, digit: u8) -> Option<u16> {
+    value
+        .unwrap_or(0)
+        .checked_mul(10)?
+        .checked_add(u16::from(digit - b'0'))
+}
+
+/// The `Mul`s in corrupted memory read from `reader`, found as it is read, like
+/// [`crate::parser::parse`] but without holding all of the input at once.
+///
+/// Only the latest chunk is kept, along with how much of an instruction split across chunks has
+/// been read so far, so memory use is bounded by the chunk size and every byte is only read once.
+#[derive(Debug)]
+pub struct MulStream<R> {
+    reader: R,
+    buf: Vec<u8>,
+    /// How much of `buf` has been scanned
+    pos: usize,
+    state: State,
+    chunk_size: usize,
+    eof: bool,
+    use_conditionals: bool,
+    enabled: bool,
+}
+
+impl<R: Read> MulStream<R> {
+    pub fn new(reader: R, use_conditionals: bool) -> Self {
+        MulStream {
+            reader,
+            buf: Vec::new(),
+            pos: 0,
+            state: State::Start,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            eof: false,
+            use_conditionals,
+            enabled: true,
+        }
+    }
+
+    pub fn with_chunk_size(self, chunk_size: usize) -> Self {
+        MulStream {
+            chunk_size: chunk_size.max(1),
+            ..self
+        }
+    }
+
+    /// Replaces the scanned chunk with the next one
+    fn fill(&mut self) -> io::Result<()> {
+        self.pos = 0;
+        self.buf.resize(self.chunk_size, 0);
+        let read = loop {
+            match self.reader.read(&mut self.buf) {
+                Ok(read) => break read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    self.buf.clear();
+                    return Err(e);
+                }
+            }
+        };
+        self.buf.truncate(read);
+        self.eof = read == 0;
+        Ok(())
+    }
+}
+
+impl<R: Read> Iterator for MulStream<R> {
+    type Item = Result<Mul, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.state == State::Start {
+                // nothing but `m` or `d` can start an instruction, so skip straight to the next one
+                self.pos = memchr2(b'm', b'd', &self.buf[self.pos..])
+                    .map_or(self.buf.len(), |skip| self.pos + skip);
+            }
+            let Some(&byte) = self.buf.get(self.pos) else {
+                // anything part way through an instruction when the input runs out is dropped
+                if self.eof {
+                    return None;
+                }
+                if let Err(e) = self.fill() {
+                    return Some(Err(e.into()));
+                }
+                continue;
+            };
+            self.pos += 1;
+            let atom;
+            (self.state, atom) = self.state.step(byte);
+            match atom {
+                Some(ParsedAtom::Mul(mul)) if self.enabled || !self.use_conditionals => {
+                    return Some(Ok(mul));
+                }
+                Some(ParsedAtom::Conditional(conditional)) => {
+                    self.enabled = conditional == Conditional::Do;
+                }
+                Some(ParsedAtom::Mul(_)) | None => {}
+            }
+        }
+    }
+}
+
+/// Scans corrupted memory read from `reader` for `Mul`s, as a [`MulStream`]
+pub fn parse_reader<R: Read>(reader: R, use_conditionals: bool) -> MulStream<R> {
+    MulStream::new(reader, use_conditionals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = include_str!("input.txt");
+    const TEST_INPUT: &str = include_str!("test_input.txt");
+
+    /// Hands out at most `max` bytes per read, to split instructions in as many places as possible
+    struct Trickle<'a> {
+        data: &'a [u8],
+        max: usize,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = buf.len().min(self.max).min(self.data.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn split_everywhere() {
+        for use_conditionals in [false, true] {
+            let expected = crate::parser::parse(TEST_INPUT, use_conditionals).unwrap();
+            for chunk_size in 1..=TEST_INPUT.len() {
+                let muls = parse_reader(TEST_INPUT.as_bytes(), use_conditionals)
+                    .with_chunk_size(chunk_size)
+                    .collect::<Result<Vec<_>, _>>()
+                    .unwrap();
+                assert_eq!(muls, expected, "chunk size {chunk_size}");
+            }
+            for max in 1..=8 {
+                let reader = Trickle {
+                    data: TEST_INPUT.as_bytes(),
+                    max,
+                };
+                let muls = parse_reader(reader, use_conditionals)
+                    .collect::<Result<Vec<_>, _>>()
+                    .unwrap();
+                assert_eq!(muls, expected, "reads of {max}");
+            }
+        }
+    }
+
+    #[test]
+    fn matches_parse() {
+        for use_conditionals in [false, true] {
+            let expected = crate::parser::parse(INPUT, use_conditionals).unwrap();
+            for chunk_size in [7, 64, 4096] {
+                let muls = parse_reader(INPUT.as_bytes(), use_conditionals)
+                    .with_chunk_size(chunk_size)
+                    .collect::<Result<Vec<_>, _>>()
+                    .unwrap();
+                assert_eq!(muls, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn incomplete_at_end() {
+        let muls = parse_reader("mul(1,2)mul(3,4".as_bytes(), false)
+            .with_chunk_size(3)
+            .collect::<Result<Vec<_>, _>>();
+        assert_eq!(muls, Ok(vec![Mul { x: 1, y: 2 }]));
+        let muls = parse_reader("mul(3,4)don'".as_bytes(), true).collect::<Vec<_>>();
+        assert_eq!(muls, vec![Ok(Mul { x: 3, y: 4 })]);
+    }
+
+    #[test]
+    fn long_digit_runs() {
+        let chunk_size = 4096;
+        let digits = "1".repeat(10 * chunk_size);
+        let zeros = "0".repeat(10 * chunk_size);
+        let input = format!("mul({digits},2)mul(2,3)mul(1,{zeros}7)mul({digits}");
+        let mut muls = parse_reader(input.as_bytes(), false).with_chunk_size(chunk_size);
+        assert_eq!(muls.next().unwrap().unwrap(), Mul { x: 2, y: 3 });
+        assert_eq!(muls.next().unwrap().unwrap(), Mul { x: 1, y: 7 });
+        assert!(muls.next().is_none());
+        assert!(muls.buf.capacity() <= chunk_size);
+    }
+
+    #[test]
+    fn read_errors() {
+        struct Broken;
+        impl Read for Broken {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::ErrorKind::BrokenPipe.into())
+            }
+        }
+        let mut muls = parse_reader(Broken, false);
+        match muls.next() {
+            Some(Err(ParseError::Io(e))) => assert_eq!(e.kind(), io::ErrorKind::BrokenPipe),
+            other => panic!("expected a read error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn errors_compare() {
+        let broken = || io::Error::from(io::ErrorKind::BrokenPipe);
+        assert_eq!(
+            ParseError::from(io::Error::new(io::ErrorKind::BrokenPipe, "pipe closed")),
+            ParseError::Io(broken())
+        );
+        assert_ne!(
+            ParseError::Io(broken()),
+            ParseError::Io(io::ErrorKind::Interrupted.into())
+        );
+        let incomplete: nom::Err<nom::error::Error<&str>> =
+            nom::Err::Incomplete(nom::Needed::Unknown);
+        assert_eq!(ParseError::from(incomplete), ParseError::Incomplete);
+    }
+}